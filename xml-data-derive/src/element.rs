@@ -4,7 +4,11 @@ use crate::attributes::{
 	string_lit,
 };
 
-use proc_macro2::Span;
+use proc_macro2::{
+	Span,
+	TokenStream,
+};
+use quote::quote;
 use syn::{
 	parse_quote,
 	spanned::Spanned,
@@ -12,6 +16,7 @@ use syn::{
 	DataStruct,
 	DeriveInput,
 	Fields,
+	Generics,
 	Ident,
 	Lit,
 	NestedMeta,
@@ -23,6 +28,27 @@ pub struct FieldAttribute {
 	pub key: String,
 	pub optional: bool,
 	pub is_string: bool,
+	pub is_qname: bool,
+	pub flag: Option<FlagValues>,
+}
+
+impl FieldAttribute {
+	/// Marker type implementing `Value` for the attribute
+	pub fn value_t(&self) -> TokenStream {
+		if self.is_qname {
+			quote!(ValueQName)
+		} else if self.is_string {
+			quote!(ValueString)
+		} else {
+			quote!(ValueDefault)
+		}
+	}
+}
+
+/// Lexical forms of a `bool` attribute
+pub struct FlagValues {
+	pub true_value: String,
+	pub false_value: String,
 }
 
 pub struct Field {
@@ -30,27 +56,197 @@ pub struct Field {
 	pub ty: Type,
 	pub span: Span,
 	pub attr: Option<FieldAttribute>,
+	/// tag of empty element representing a `bool` field
+	pub flag: Option<String>,
+	/// namespace of attribute or flag element (flags default to the container namespace)
+	pub ns: Option<String>,
+	/// preferred prefix for `ns`
+	pub prefix: Option<String>,
+	/// inner text is serialized as CDATA sections
+	pub cdata: bool,
+	/// inner text is parsed from and serialized as comments
+	pub comment: bool,
+}
+
+impl Field {
+	/// Whether field is parsed through `Inner` (not an attribute or flag)
+	pub fn is_inner(&self) -> bool {
+		self.attr.is_none() && self.flag.is_none()
+	}
+
+	/// Namespace of attribute or flag element
+	pub fn namespace<'a>(&'a self, meta: &'a Meta) -> Option<&'a str> {
+		match (&self.ns, &self.flag) {
+			(Some(ns), _) => Some(ns),
+			(None, Some(_)) => meta.ns.as_deref(),
+			(None, None) => None,
+		}
+	}
+}
+
+/// `Some("...")` or `None` tokens
+pub fn option_str(value: Option<&str>) -> TokenStream {
+	match value {
+		Some(value) => quote!(Some(#value)),
+		None => quote!(None),
+	}
+}
+
+/// Namespace and preferred prefix given as `ns = "..."` and `prefix = "..."`
+#[derive(Default)]
+struct NamespaceArgs {
+	ns: Option<String>,
+	prefix: Option<String>,
+}
+
+impl NamespaceArgs {
+	/// Try to parse `ns = "..."` or `prefix = "..."`; returns whether it was one of those
+	fn parse(&mut self, nested: &NestedMeta) -> bool {
+		let nv = match nested {
+			NestedMeta::Meta(syn::Meta::NameValue(nv)) => nv,
+			_ => return false,
+		};
+		let target = if nv.path.is_ident("ns") {
+			&mut self.ns
+		} else if nv.path.is_ident("prefix") {
+			&mut self.prefix
+		} else {
+			return false;
+		};
+		assert!(target.is_none(), "Already have #[xml_data(ns/prefix)]");
+		match &nv.lit {
+			Lit::Str(v) => *target = Some(v.value()),
+			_ => panic!("invalid literal; expected string"),
+		}
+		true
+	}
+
+	fn merge(&mut self, other: Self) {
+		for (target, value) in [(&mut self.ns, other.ns), (&mut self.prefix, other.prefix)] {
+			if value.is_some() {
+				assert!(target.is_none(), "Already have #[xml_data(ns/prefix)]");
+				*target = value;
+			}
+		}
+	}
+
+	fn check(&self) {
+		assert!(
+			self.prefix.is_none() || self.ns.is_some(),
+			"#[xml_data(prefix)] requires #[xml_data(ns)]"
+		);
+	}
+}
+
+/// Parse `attr("key", ns = "...", prefix = "...")` or `flag(...)`
+fn parse_name(meta: &syn::Meta, ns_args: &mut NamespaceArgs) -> Option<String> {
+	let list = match meta {
+		syn::Meta::Path(_) => return None,
+		syn::Meta::List(l) => l,
+		syn::Meta::NameValue(_) => return string_lit(meta),
+	};
+	let mut name = None;
+	for nested in &list.nested {
+		match nested {
+			NestedMeta::Lit(Lit::Str(n)) => {
+				assert!(name.is_none(), "only single name allowed");
+				name = Some(n.value());
+			},
+			nested if ns_args.parse(nested) => (),
+			_ => panic!("invalid argument in #[xml_data(...(...))]"),
+		}
+	}
+	name
+}
+
+/// Whether type is `String`, `Cow<str>` or `&str` (types that use `ValueString` by default)
+fn is_string_type(ty: &Type) -> bool {
+	match ty {
+		Type::Path(p) => {
+			p.qself.is_none()
+				&& (p.path.is_ident("String")
+					|| p.path.segments.last().is_some_and(|s| s.ident == "Cow"))
+		},
+		Type::Reference(r) => {
+			matches!(&*r.elem, Type::Path(p) if p.qself.is_none() && p.path.is_ident("str"))
+		},
+		_ => false,
+	}
+}
+
+/// Whether type is `QName` (uses `ValueQName` by default)
+fn is_qname_type(ty: &Type) -> bool {
+	matches!(ty, Type::Path(p) if p.qself.is_none() && p.path.segments.last().is_some_and(|s| s.ident == "QName"))
+}
+
+fn parse_attr_flag(meta: &syn::Meta, ns_args: &mut NamespaceArgs) -> (Option<String>, FlagValues) {
+	let mut key = None;
+	let mut values = FlagValues {
+		true_value: "true".into(),
+		false_value: "false".into(),
+	};
+	let list = match meta {
+		syn::Meta::Path(_) => return (key, values),
+		syn::Meta::List(l) => l,
+		syn::Meta::NameValue(_) => panic!("expected #[xml_data(attr_flag(...))]"),
+	};
+	for nested in &list.nested {
+		match nested {
+			NestedMeta::Lit(Lit::Str(k)) => {
+				assert!(
+					key.is_none(),
+					"Already have #[xml_data(attr_flag(\"...\"))]"
+				);
+				key = Some(k.value());
+			},
+			nested if ns_args.parse(nested) => (),
+			NestedMeta::Meta(syn::Meta::NameValue(nv)) => {
+				let value = match &nv.lit {
+					Lit::Str(v) => v.value(),
+					_ => panic!("invalid literal; expected string"),
+				};
+				if nv.path.is_ident("true_value") {
+					values.true_value = value;
+				} else if nv.path.is_ident("false_value") {
+					values.false_value = value;
+				} else {
+					panic!("Unknown #[xml_data(attr_flag(...))] argument");
+				}
+			},
+			_ => panic!("invalid argument in #[xml_data(attr_flag(...))]"),
+		}
+	}
+	assert!(
+		values.true_value != values.false_value,
+		"true_value and false_value must differ"
+	);
+	(key, values)
 }
 
 impl Field {
 	fn parse(name: Ident, field: &syn::Field) -> Self {
 		let mut is_attr = false;
 		let mut attr_key = None;
+		let mut attr_flag = None;
+		let mut flag = None;
 		let attr_optional = false;
-		let mut attr_is_string = if let Type::Path(p) = &field.ty {
-			p.qself.is_none() && p.path.is_ident("String")
-		} else {
-			false
-		};
+		let mut attr_is_string = is_string_type(&field.ty);
+		let mut cdata = false;
+		let mut comment = false;
+		let mut ns_args = NamespaceArgs::default();
 
 		for attr in all_attributes(&field.attrs) {
+			if ns_args.parse(&attr) {
+				continue;
+			}
 			let m = match attr {
 				NestedMeta::Lit(_) => panic!("invalid literal in #[xml_data(..., ...)]"),
 				NestedMeta::Meta(m) => m,
 			};
+			let mut nested_ns_args = NamespaceArgs::default();
 			if m.path().is_ident("attr") {
 				is_attr = true;
-				let new_attr_key = string_lit(&m);
+				let new_attr_key = parse_name(&m, &mut nested_ns_args);
 				if new_attr_key.is_some() {
 					assert!(
 						attr_key.is_none(),
@@ -61,16 +257,58 @@ impl Field {
 			} else if m.path().is_ident("attr_string") {
 				is_attr = true;
 				attr_is_string = true;
+			} else if m.path().is_ident("attr_flag") {
+				is_attr = true;
+				let (new_attr_key, values) = parse_attr_flag(&m, &mut nested_ns_args);
+				if new_attr_key.is_some() {
+					assert!(
+						attr_key.is_none(),
+						"Already have #[xml_data(attr(\"...\"))]"
+					);
+					attr_key = new_attr_key;
+				}
+				attr_flag = Some(values);
+			} else if m.path().is_ident("cdata") {
+				cdata = true;
+			} else if m.path().is_ident("comment") {
+				comment = true;
+			} else if m.path().is_ident("flag") {
+				assert!(flag.is_none(), "Already have #[xml_data(flag)]");
+				flag =
+					Some(parse_name(&m, &mut nested_ns_args).unwrap_or_else(|| name.to_string()));
 			} else {
 				panic!("Unknown #[xml_data] attribute");
 			}
+			ns_args.merge(nested_ns_args);
 		}
+		ns_args.check();
+		assert!(
+			ns_args.ns.is_none() || is_attr || flag.is_some(),
+			"#[xml_data(ns)] only supported for attributes and flags (other fields use the \
+			 namespace of their type)"
+		);
+
+		assert!(
+			!(is_attr && flag.is_some()),
+			"#[xml_data(flag)] can't be combined with attributes; use #[xml_data(attr_flag)]"
+		);
+
+		assert!(
+			!((cdata || comment) && (is_attr || flag.is_some())),
+			"#[xml_data(cdata)] and #[xml_data(comment)] are only supported for inner text"
+		);
+		assert!(
+			!(cdata && comment),
+			"#[xml_data(cdata)] can't be combined with #[xml_data(comment)]"
+		);
 
 		let attr = if is_attr {
 			Some(FieldAttribute {
 				key: attr_key.unwrap_or_else(|| name.to_string()),
 				optional: attr_optional,
 				is_string: attr_is_string,
+				is_qname: !attr_is_string && is_qname_type(&field.ty),
+				flag: attr_flag,
 			})
 		} else {
 			None
@@ -81,6 +319,11 @@ impl Field {
 			span: field.span(),
 			ty: field.ty.clone(),
 			attr,
+			flag,
+			ns: ns_args.ns,
+			prefix: ns_args.prefix,
+			cdata,
+			comment,
 		}
 	}
 }
@@ -88,7 +331,12 @@ impl Field {
 pub struct Meta {
 	pub xml_data_crate: Path,
 	pub name: Ident,
+	pub generics: Generics,
 	pub tag: String,
+	/// namespace of the element (and default for flags)
+	pub ns: Option<String>,
+	/// preferred prefix for `ns`
+	pub prefix: Option<String>,
 	pub fields: Vec<Field>,
 	pub ignore_unknown_attribute: bool,
 	pub ignore_unknown_element: bool,
@@ -102,8 +350,12 @@ impl Meta {
 		let mut ignore_unknown_attribute = false;
 		let mut ignore_unknown_element = false;
 		let mut ignore_text = false;
+		let mut ns_args = NamespaceArgs::default();
 
 		for attr in all_attributes(&element.attrs) {
+			if ns_args.parse(&attr) {
+				continue;
+			}
 			let m = match attr {
 				NestedMeta::Lit(Lit::Str(t)) => {
 					assert!(impl_element, "Tag not supported for `Inner`");
@@ -134,6 +386,7 @@ impl Meta {
 				panic!("unknown #[xml_data()] attribute");
 			}
 		}
+		ns_args.check();
 		// tag is ignored for `Inner`
 		let tag = tag.unwrap_or_else(|| element.ident.to_string());
 		let xml_data_crate = xml_data_crate.unwrap_or_else(|| parse_quote! { xml_data });
@@ -145,7 +398,7 @@ impl Meta {
 			}) => n
 				.named
 				.iter()
-				.map(|field| Field::parse(field.ident.clone().unwrap(), &field))
+				.map(|field| Field::parse(field.ident.clone().unwrap(), field))
 				.collect(),
 			Data::Struct(DataStruct {
 				fields: Fields::Unit,
@@ -160,7 +413,10 @@ impl Meta {
 		Self {
 			xml_data_crate,
 			name: element.ident.clone(),
+			generics: element.generics.clone(),
 			tag,
+			ns: ns_args.ns,
+			prefix: ns_args.prefix,
 			fields,
 			ignore_unknown_attribute,
 			ignore_unknown_element,
@@ -4,7 +4,11 @@
 //! - `#[xml_data(tag("..."))]`: XML tag (only for deriving `Element`); defaults to struct name
 //! - `#[xml_data(crate(...))]`: Name of `xml-data` crate in local scope; defaults to `xml_data`
 //! - `#[xml_data(ignore_unknown)]`: Ignore unhandled/unknown attributes, inner nodes and inner text
-//!   (only for deriving `Element`; `Inner` never fails for unknown data)
+//!   (only for deriving `Element`; `Inner` never fails for unknown data); ignored data is
+//!   recorded as warnings (see `xml_data::parser::ParseContext::warn`)
+//! - `#[xml_data(ns = "...")]`: Namespace of the element (only for deriving `Element`), also the
+//!   default namespace for flag elements
+//! - `#[xml_data(prefix = "...")]`: Preferred prefix for the namespace when serializing
 //!
 //! And the following attributes on struct fields:
 //! - `#[xml(attr)]: Mark field as attribute for containing XML element (only for deriving
@@ -13,9 +17,34 @@
 //!   for deriving `Element`)
 //! - `#[xml(attr_string)]: Mark field as string attribute (using `ValueString` instead of
 //!   `ValueDefault`) for containing XML element (only for deriving `Element`)
+//! - `#[xml(attr_flag)]: Mark `bool` field as attribute which is only written when `true` (a
+//!   missing attribute is parsed as `false`); the key can be given as in `attr("...")`, and the
+//!   accepted values with `true_value = "..."` (default `"true"`) and `false_value = "..."`
+//!   (default `"false"`) (only for deriving `Element`)
+//! - `#[xml(flag)]: Mark `bool` field as empty element (with the field name as tag) which is only
+//!   written when `true` (a missing element is parsed as `false`)
+//! - `#[xml(flag("..."))]: Mark `bool` field as empty element with the given tag
+//! - `#[xml(cdata)]`: Serialize inner text of the field as CDATA sections (parsing doesn't
+//!   distinguish CDATA from other text)
+//! - `#[xml(comment)]`: Parse the field from comments (instead of text) and serialize its inner
+//!   text as comments
+//! - `#[xml(ns = "...")]`: Namespace of an attribute or flag element; can also be given inside
+//!   `attr(...)`, `attr_flag(...)` and `flag(...)`, e.g. `attr(ns = "...", "key")` (unqualified
+//!   attributes have no namespace; flags default to the namespace of the container)
+//! - `#[xml(prefix = "...")]`: Preferred prefix for the namespace of an attribute or flag when
+//!   serializing
 //!
 //! Multiple attributes can be combined like `#[xml(tag("..."), ignore_unknown)]`.
 //!
+//! `String`, `Cow<str>` and `&str` attributes automatically use `ValueString`, `QName` attributes
+//! use `ValueQName` (resolving and declaring namespace prefixes in the value).  Structs can have
+//! lifetime parameters to borrow `Cow<'a, str>` and `&'a str` fields from the parser input.
+//!
+//! Other fields are parsed using `xml_data::parser::Inner`; e.g. `Vec<xml_data::Result<E>>` keeps
+//! elements that failed to parse as errors instead of failing the containing element.
+//! Comments and processing instructions are offered to those fields too; if no field takes them
+//! comments are ignored and processing instructions are rejected.
+//!
 #![warn(missing_docs)]
 #![doc(html_root_url = "https://docs.rs/xml-data-derive/0.0.1")]
 
@@ -1,14 +1,63 @@
 use crate::element::{
+	option_str,
 	Field,
 	FieldAttribute,
+	FlagValues,
 	Meta,
 };
-use proc_macro2::TokenStream;
+use proc_macro2::{
+	TokenStream,
+	TokenTree,
+};
 use quote::{
 	quote,
 	quote_spanned,
+	ToTokens,
+};
+use syn::{
+	parse_quote,
+	GenericParam,
+	Generics,
+	Lifetime,
+	LifetimeDef,
 };
 
+/// Lifetime of the input data in the generated parser implementation
+fn input_lifetime() -> Lifetime {
+	parse_quote!('__xml)
+}
+
+fn contains_static_lifetime(tokens: TokenStream) -> bool {
+	tokens.into_iter().any(|token| match token {
+		TokenTree::Ident(ident) => ident == "static",
+		TokenTree::Group(group) => contains_static_lifetime(group.stream()),
+		_ => false,
+	})
+}
+
+/// Generics for the parser implementation: additional lifetime for the input data, which must
+/// outlive all lifetimes of the struct (including `'static` if used in a field type).
+fn parser_generics(meta: &Meta) -> Generics {
+	let generics = &meta.generics;
+	let input = input_lifetime();
+	let mut input_param: LifetimeDef = parse_quote!(#input);
+	input_param
+		.bounds
+		.extend(generics.lifetimes().map(|l| l.lifetime.clone()));
+	if meta
+		.fields
+		.iter()
+		.any(|field| contains_static_lifetime(field.ty.to_token_stream()))
+	{
+		input_param.bounds.push(parse_quote!('static));
+	}
+	let mut parser_generics = generics.clone();
+	parser_generics
+		.params
+		.insert(0, GenericParam::Lifetime(input_param));
+	parser_generics
+}
+
 pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 	let Meta {
 		xml_data_crate,
@@ -16,6 +65,11 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 		tag,
 		..
 	} = meta;
+	let input = input_lifetime();
+	let ns = option_str(meta.ns.as_deref());
+	let parser_generics = parser_generics(meta);
+	let (impl_generics, state_ty_generics, where_clause) = parser_generics.split_for_impl();
+	let (_, ty_generics, _) = meta.generics.split_for_impl();
 
 	let parse_success = if impl_element {
 		quote! { Ok(()) }
@@ -39,10 +93,14 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 						#name: Option<#ty>,
 					}
 				}
+			} else if field.flag.is_some() {
+				quote_spanned! {*span=>
+					#name: bool,
+				}
 			} else {
 				// inner
 				quote_spanned! {*span=>
-					#name: <#ty as Inner>::ParseState,
+					#name: <#ty as Inner<#input>>::ParseState,
 				}
 			}
 		})
@@ -58,6 +116,11 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 					quote_spanned! {*span=>
 						#name: self.#name,
 					}
+				} else if attr.flag.is_some() {
+					// missing flag attribute means `false`
+					quote_spanned! {*span=>
+						#name: self.#name.unwrap_or(false),
+					}
 				} else {
 					quote_spanned! {*span=>
 						#name: match self.#name {
@@ -66,6 +129,10 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 						},
 					}
 				}
+			} else if field.flag.is_some() {
+				quote_spanned! {*span=>
+					#name: self.#name,
+				}
 			} else {
 				// inner
 				quote_spanned! {*span=>
@@ -82,14 +149,27 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 			if let Some(attr) = &field.attr {
 				let FieldAttribute { key: attr_key, .. } = attr;
 				let Field { name, span, .. } = field;
-				let value_t = if attr.is_string {
-					quote!(ValueString)
-				} else {
-					quote!(ValueDefault)
-				};
+				let attr_ns = option_str(field.namespace(meta));
+				if let Some(FlagValues {
+					true_value,
+					false_value,
+				}) = &attr.flag
+				{
+					return Some(quote_spanned! {*span=>
+						if key == Name::new(#attr_ns, #attr_key) && self.#name.is_none() {
+							self.#name = Some(match &*value {
+								#true_value => true,
+								#false_value => false,
+								_ => return Err(errors::invalid_flag_value(#attr_key, &value)),
+							});
+							return Ok(())
+						}
+					});
+				}
+				let value_t = attr.value_t();
 				Some(quote_spanned! {*span=>
-					if #attr_key == key && self.#name.is_none() {
-						self.#name = Some(#value_t::parse_value(value)?);
+					if key == Name::new(#attr_ns, #attr_key) && self.#name.is_none() {
+						self.#name = Some(#value_t::parse_value(value, context)?);
 						return Ok(())
 					}
 				})
@@ -102,7 +182,17 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 		.fields
 		.iter()
 		.filter_map(|field| {
-			if field.attr.is_none() {
+			if let Some(flag_tag) = &field.flag {
+				let Field { name, span, .. } = field;
+				let flag_ns = option_str(field.namespace(meta));
+				Some(quote_spanned! {*span=>
+					if tag == Name::new(#flag_ns, #flag_tag) && !self.#name {
+						parser.parse_element_state(&mut EmptyElement)?;
+						self.#name = true;
+						return #parse_success;
+					}
+				})
+			} else if field.attr.is_none() && !field.comment {
 				let Field { name, span, .. } = field;
 				Some(quote_spanned! {*span=>
 					let parser = match self.#name.parse_inner_node(tag, parser)? {
@@ -119,10 +209,10 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 		.fields
 		.iter()
 		.filter_map(|field| {
-			if field.attr.is_none() {
+			if field.is_inner() && !field.comment {
 				let Field { name, span, .. } = field;
 				Some(quote_spanned! {*span=>
-					let text = match self.#name.parse_inner_text(text)? {
+					let text = match self.#name.parse_inner_text(text, context)? {
 						InnerParseResult::Next(t) => t,
 						InnerParseResult::Success => return #parse_success,
 					};
@@ -133,59 +223,169 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 		})
 		.collect();
 
+	let el_inner_comment: TokenStream = meta
+		.fields
+		.iter()
+		.filter_map(|field| {
+			if field.is_inner() {
+				let Field { name, span, .. } = field;
+				// comment fields take comments as text
+				let parse = if field.comment {
+					quote!(parse_inner_text)
+				} else {
+					quote!(parse_inner_comment)
+				};
+				Some(quote_spanned! {*span=>
+					let text = match self.#name.#parse(text, context)? {
+						InnerParseResult::Next(t) => t,
+						InnerParseResult::Success => return #parse_success,
+					};
+				})
+			} else {
+				None
+			}
+		})
+		.collect();
+	let el_inner_pi: TokenStream = meta
+		.fields
+		.iter()
+		.filter_map(|field| {
+			if field.is_inner() && !field.comment {
+				let Field { name, span, .. } = field;
+				Some(quote_spanned! {*span=>
+					let pi = match self.#name.parse_inner_pi(pi, context)? {
+						InnerParseResult::Next(pi) => pi,
+						InnerParseResult::Success => return #parse_success,
+					};
+				})
+			} else {
+				None
+			}
+		})
+		.collect();
+
+	// names still accepted in the current state (for error messages)
+	let expected_attrs: TokenStream = meta
+		.fields
+		.iter()
+		.filter_map(|field| {
+			let attr = field.attr.as_ref()?;
+			let FieldAttribute { key: attr_key, .. } = attr;
+			let Field { name, span, .. } = field;
+			Some(quote_spanned! {*span=>
+				if self.#name.is_none() {
+					expected.push(#attr_key);
+				}
+			})
+		})
+		.collect();
+	let expected_tags: TokenStream = meta
+		.fields
+		.iter()
+		.filter_map(|field| {
+			let Field { name, span, .. } = field;
+			if let Some(flag_tag) = &field.flag {
+				let flag_ns = option_str(field.namespace(meta));
+				Some(quote_spanned! {*span=>
+					if !self.#name {
+						expected.push(Name::new(#flag_ns, #flag_tag));
+					}
+				})
+			} else if field.attr.is_none() {
+				Some(quote_spanned! {*span=>
+					self.#name.expected_tags(expected);
+				})
+			} else {
+				None
+			}
+		})
+		.collect();
+
 	let handle_unknown_attribute = if meta.ignore_unknown_attribute {
 		quote! {
-			let _ = key;
 			let _ = value;
+			context.warn(WarningKind::IgnoredAttribute { key: key.to_string() });
 			return Ok(());
 		}
 	} else {
 		quote! {
-			let _ = value;
-			return Err(errors::unexpected_attribute(key));
+			let _ = (value, context);
+			let expected: &mut Vec<&'static str> = &mut Vec::new();
+			#expected_attrs
+			return Err(errors::unexpected_attribute_expected(&key.to_string(), expected));
 		}
 	};
-	let handle_unknown_element = if meta.ignore_unknown_attribute {
+	let handle_unknown_element = if meta.ignore_unknown_element {
 		quote! {
-			parser.parse_element_state(&mut IgnoreElement)
+			IgnoreElement::ignore(tag, parser)
 		}
 	} else {
 		quote! {
 			let _ = parser;
-			return Err(errors::unexpected_element(tag));
+			let expected: &mut Vec<Name<'static>> = &mut Vec::new();
+			#expected_tags
+			return Err(errors::unexpected_element_expected(&tag.to_string(), expected));
 		}
 	};
 	let handle_text = if meta.ignore_text {
 		quote! {
-			let _ = text;
+			let text = text.trim();
+			if !text.is_empty() {
+				context.warn(WarningKind::IgnoredText { text: text.to_string() });
+			}
 		}
 	} else {
 		quote! {
+			let _ = context;
 			if !text.trim().is_empty() {
 				return Err(errors::unexpected_text());
 			}
 		}
 	};
 
+	let state_defaults: TokenStream = meta
+		.fields
+		.iter()
+		.map(|field| {
+			let Field { name, span, .. } = field;
+			quote_spanned! {*span=>
+				#name: Default::default(),
+			}
+		})
+		.collect();
+
 	let actual_impl = if impl_element {
 		quote! {
-			impl FixedElementState for State {
-				type Output = #name;
+			impl #impl_generics FixedElementState<#input> for State #state_ty_generics #where_clause {
+				type Output = #name #ty_generics;
 
 				const TAG: &'static str = #tag;
+				const NAMESPACE: Option<&'static str> = #ns;
 
-				fn parse_element_attribute(&mut self, key: &str, value: Cow<'_, str>) -> Result<()> {
+				fn parse_element_attribute(&mut self, key: Name<'_>, value: Cow<#input, str>, context: &mut dyn ParseContext) -> Result<()> {
 					#el_attrs
 					#handle_unknown_attribute
 				}
 
-				fn parse_element_inner_text(&mut self, text: Cow<'_, str>) -> Result<()> {
+				fn parse_element_inner_text(&mut self, text: Cow<#input, str>, context: &mut dyn ParseContext) -> Result<()> {
 					#el_inner_text
 					#handle_text
 					Ok(())
 				}
 
-				fn parse_element_inner_node<P: ElementParser>(&mut self, tag: &str, parser: P) -> Result<()> {
+				fn parse_element_inner_comment(&mut self, text: Cow<#input, str>, context: &mut dyn ParseContext) -> Result<()> {
+					#el_inner_comment
+					let _ = (text, context);
+					Ok(())
+				}
+
+				fn parse_element_inner_pi(&mut self, pi: ProcessingInstruction<#input>, context: &mut dyn ParseContext) -> Result<()> {
+					#el_inner_pi
+					let _ = (pi, context);
+					Err(errors::unexpected_pi())
+				}
+
+				fn parse_element_inner_node<P: ElementParser<#input>>(&mut self, tag: Name<'_>, parser: P) -> Result<()> {
 					#el_inner_node
 					#handle_unknown_element
 				}
@@ -197,25 +397,43 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 				}
 			}
 
-			impl Element for #name {
-				type ParseState = State;
+			impl #impl_generics Element<#input> for #name #ty_generics #where_clause {
+				type ParseState = State #state_ty_generics;
 			}
 		}
 	} else {
 		quote! {
-			impl InnerState for State {
-				type Output = #name;
+			impl #impl_generics InnerState<#input> for State #state_ty_generics #where_clause {
+				type Output = #name #ty_generics;
 
-				fn parse_inner_text<'t>(&mut self, text: Cow<'t, str>) -> Result<InnerParseResult<Cow<'t, str>>> {
+				fn parse_inner_text(&mut self, text: Cow<#input, str>, context: &mut dyn ParseContext) -> Result<InnerParseResult<Cow<#input, str>>> {
 					#el_inner_text
+					let _ = context;
+					Ok(InnerParseResult::Next(text))
+				}
+
+				fn parse_inner_comment(&mut self, text: Cow<#input, str>, context: &mut dyn ParseContext) -> Result<InnerParseResult<Cow<#input, str>>> {
+					#el_inner_comment
+					let _ = context;
 					Ok(InnerParseResult::Next(text))
 				}
 
-				fn parse_inner_node<P: ElementParser>(&mut self, tag: &str, parser: P) -> Result<InnerParseResult<P>> {
+				fn parse_inner_pi(&mut self, pi: ProcessingInstruction<#input>, context: &mut dyn ParseContext) -> Result<InnerParseResult<ProcessingInstruction<#input>>> {
+					#el_inner_pi
+					let _ = context;
+					Ok(InnerParseResult::Next(pi))
+				}
+
+				fn parse_inner_node<P: ElementParser<#input>>(&mut self, tag: Name<'_>, parser: P) -> Result<InnerParseResult<P>> {
 					#el_inner_node
 					Ok(InnerParseResult::Next(parser))
 				}
 
+				fn expected_tags(&self, expected: &mut Vec<Name<'static>>) {
+					let _ = &expected;
+					#expected_tags
+				}
+
 				fn parse_inner_finish(self) -> Result<Self::Output> {
 					Ok(#name {
 						#finish
@@ -223,12 +441,12 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 				}
 			}
 
-			impl Inner for #name {
-				type ParseState = State;
+			impl #impl_generics Inner<#input> for #name #ty_generics #where_clause {
+				type ParseState = State #state_ty_generics;
 			}
 
-			impl Inner for Option<#name> {
-				type ParseState = ParseInnerOptional<State>;
+			impl #impl_generics Inner<#input> for Option<#name #ty_generics> #where_clause {
+				type ParseState = ParseInnerOptional<State #state_ty_generics>;
 			}
 		}
 	};
@@ -242,24 +460,40 @@ pub fn build_parser(meta: &Meta, impl_element: bool) -> TokenStream {
 					ElementParser,
 					Element,
 					ElementDefaultParseState,
+					EmptyElement,
 					IgnoreElement,
 					Inner,
 					InnerState,
 					InnerParseResult,
+					ParseContext,
 					ParseInnerOptional,
+					ProcessingInstruction,
 					Value,
 					ValueString,
 					ValueDefault,
+					ValueQName,
+					WarningKind,
 				},
 				errors,
+				Name,
 				Result,
 			};
 			use std::borrow::Cow;
+			use std::marker::PhantomData;
 			use std::string::ToString;
 
-			#[derive(Default)]
-			pub struct State {
+			pub struct State #impl_generics #where_clause {
 				#state_fields
+				_phantom: PhantomData<fn() -> (&#input (), #name #ty_generics)>,
+			}
+
+			impl #impl_generics Default for State #state_ty_generics #where_clause {
+				fn default() -> Self {
+					Self {
+						#state_defaults
+						_phantom: PhantomData,
+					}
+				}
 			}
 
 			#actual_impl
@@ -1,4 +1,5 @@
 use crate::element::{
+	option_str,
 	Field,
 	FieldAttribute,
 	Meta,
@@ -16,6 +17,9 @@ pub fn build_serialize(meta: &Meta, impl_element: bool) -> TokenStream {
 		tag,
 		..
 	} = meta;
+	let (impl_generics, ty_generics, where_clause) = meta.generics.split_for_impl();
+	let ns = option_str(meta.ns.as_deref());
+	let prefix = option_str(meta.prefix.as_deref());
 
 	let serializer = if impl_element {
 		quote! { &mut serializer }
@@ -23,6 +27,31 @@ pub fn build_serialize(meta: &Meta, impl_element: bool) -> TokenStream {
 		quote! { serializer }
 	};
 
+	// preferred prefixes for namespaces of attributes and flags
+	let declarations: TokenStream = meta
+		.fields
+		.iter()
+		.filter_map(|field| {
+			let Field { span, .. } = field;
+			let ns = field.ns.as_ref()?;
+			let prefix = field.prefix.as_ref()?;
+			Some(quote_spanned! {*span=>
+				serializer.declare_namespace(#prefix, #ns)?;
+			})
+		})
+		.chain(
+			// for flags in `Inner` (`Element` declares the prefix for its own namespace)
+			meta.prefix
+				.as_ref()
+				.filter(|_| !impl_element)
+				.map(|prefix| {
+					let ns = &meta.ns;
+					quote! {
+						serializer.declare_namespace(#prefix, #ns)?;
+					}
+				}),
+		)
+		.collect();
 	let el_attrs: TokenStream = meta
 		.fields
 		.iter()
@@ -33,20 +62,27 @@ pub fn build_serialize(meta: &Meta, impl_element: bool) -> TokenStream {
 				}
 				let FieldAttribute { key: attr_key, .. } = attr;
 				let Field { name, span, .. } = field;
-				let value_t = if attr.is_string {
-					quote!(ValueString)
-				} else {
-					quote!(ValueDefault)
-				};
+				let attr_ns = option_str(field.namespace(meta));
+				if let Some(flag) = &attr.flag {
+					let true_value = &flag.true_value;
+					return Some(quote_spanned! {*span=>
+						if self.#name {
+							serializer.serialize_attribute(Name::new(#attr_ns, #attr_key), Cow::Borrowed(#true_value))?;
+						}
+					});
+				}
+				let value_t = attr.value_t();
 				Some(if attr.optional {
 					quote_spanned! {*span=>
 						if let Some(#name) = &self.#name {
-							serializer.serialize_attribute(#attr_key, #value_t::serialize_value(#name)?)?;
+							let value = #value_t::serialize_value_for(#name, #serializer).map_err(|e| e.in_attribute(#attr_key))?;
+							serializer.serialize_attribute(Name::new(#attr_ns, #attr_key), value)?;
 						}
 					}
 				} else {
 					quote_spanned! {*span=>
-						serializer.serialize_attribute(#attr_key, #value_t::serialize_value(&self.#name)?)?;
+						let value = #value_t::serialize_value_for(&self.#name, #serializer).map_err(|e| e.in_attribute(#attr_key))?;
+						serializer.serialize_attribute(Name::new(#attr_ns, #attr_key), value)?;
 					}
 				})
 			} else {
@@ -58,10 +94,28 @@ pub fn build_serialize(meta: &Meta, impl_element: bool) -> TokenStream {
 		.fields
 		.iter()
 		.filter_map(|field| {
-			if field.attr.is_none() {
+			if let Some(flag_tag) = &field.flag {
 				let Field { name, span, .. } = field;
+				let flag_ns = option_str(field.namespace(meta));
 				Some(quote_spanned! {*span=>
-					self.#name.serialize_elements(#serializer)?;
+					if self.#name {
+						serializer.serialize_element(&EmptyElement(Name::new(#flag_ns, #flag_tag)))?;
+					}
+				})
+			} else if field.attr.is_none() {
+				let Field { name, span, .. } = field;
+				Some(if field.cdata {
+					quote_spanned! {*span=>
+						self.#name.serialize_elements(&mut CDataText(&mut *#serializer))?;
+					}
+				} else if field.comment {
+					quote_spanned! {*span=>
+						self.#name.serialize_elements(&mut CommentText(&mut *#serializer))?;
+					}
+				} else {
+					quote_spanned! {*span=>
+						self.#name.serialize_elements(#serializer)?;
+					}
 				})
 			} else {
 				None
@@ -71,10 +125,13 @@ pub fn build_serialize(meta: &Meta, impl_element: bool) -> TokenStream {
 
 	let actual_impl = if impl_element {
 		quote! {
-			impl FixedElement for #name {
+			impl #impl_generics FixedElement for #name #ty_generics #where_clause {
 				const TAG: &'static str = #tag;
+				const NAMESPACE: Option<&'static str> = #ns;
+				const PREFIX: Option<&'static str> = #prefix;
 
 				fn serialize<S: Serializer>(&self, mut serializer: S) -> Result<()> {
+					#declarations
 					#el_attrs
 					#el_inner
 					Ok(())
@@ -83,8 +140,9 @@ pub fn build_serialize(meta: &Meta, impl_element: bool) -> TokenStream {
 		}
 	} else {
 		quote! {
-			impl Inner for #name {
+			impl #impl_generics Inner for #name #ty_generics #where_clause {
 				fn serialize_elements<S: Serializer>(&self, serializer: &mut S) -> Result<()> {
+					#declarations
 					#el_inner
 					Ok(())
 				}
@@ -96,15 +154,21 @@ pub fn build_serialize(meta: &Meta, impl_element: bool) -> TokenStream {
 		const _: () = {
 			use #xml_data_crate::{
 				serializer::{
+					CDataText,
+					CommentText,
+					EmptyElement,
 					FixedElement,
 					Inner,
 					Serializer,
 					Value,
 					ValueString,
 					ValueDefault,
+					ValueQName,
 				},
+				Name,
 				Result,
 			};
+			use std::borrow::Cow;
 
 			#actual_impl
 		};
@@ -0,0 +1,193 @@
+//! Full XML documents: the root element with declaration, DOCTYPE, comments and processing
+//! instructions around it
+//!
+//! Parser and serializer adaptors handle [`Document`] separately (e.g.
+//! [`Parser::parse_full_document`][`crate::quick_xml::Parser::parse_full_document`]), as it isn't
+//! an element.
+
+use crate::{
+	errors,
+	Result,
+};
+use std::{
+	fmt,
+	str::FromStr,
+};
+
+/// XML version: decides which characters can appear in a document
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+	/// XML 1.0 (also assumed for documents without declaration)
+	#[default]
+	V1_0,
+	/// XML 1.1
+	V1_1,
+}
+
+impl Version {
+	/// Version as written in the declaration
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::V1_0 => "1.0",
+			Self::V1_1 => "1.1",
+		}
+	}
+
+	/// Whether character can appear in a document (possibly only as character reference, see
+	/// [`Version::is_restricted`])
+	pub fn is_char(self, c: char) -> bool {
+		match c {
+			'\t' | '\n' | '\r' => true,
+			'\u{0}' | '\u{FFFE}' | '\u{FFFF}' => false,
+			'\u{1}'..='\u{1F}' => self == Self::V1_1,
+			_ => true,
+		}
+	}
+
+	/// Whether character can only appear as character reference (`&#x1;`)
+	///
+	/// These are the "restricted" control characters of XML 1.1; XML 1.0 has none (it doesn't
+	/// allow the C0 controls at all).
+	pub fn is_restricted(self, c: char) -> bool {
+		self == Self::V1_1
+			&& matches!(c, '\u{1}'..='\u{8}' | '\u{B}' | '\u{C}' | '\u{E}'..='\u{1F}' | '\u{7F}'..='\u{84}' | '\u{86}'..='\u{9F}')
+	}
+
+	/// Fail on the first character that can't appear literally in a document
+	pub fn check_chars(self, text: &str) -> Result<()> {
+		match text
+			.chars()
+			.find(|&c| !self.is_char(c) || self.is_restricted(c))
+		{
+			Some(c) => Err(errors::invalid_character(c)),
+			None => Ok(()),
+		}
+	}
+}
+
+impl fmt::Display for Version {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl FromStr for Version {
+	type Err = crate::Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s {
+			"1.0" => Ok(Self::V1_0),
+			"1.1" => Ok(Self::V1_1),
+			_ => Err(errors::invalid_value(format!(
+				"unsupported XML version: {:?}",
+				s
+			))),
+		}
+	}
+}
+
+/// XML declaration (`<?xml version="1.0" encoding="utf-8" standalone="yes"?>`)
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Declaration {
+	/// XML version (e.g. `1.0`)
+	pub version: String,
+	/// Declared encoding
+	pub encoding: Option<String>,
+	/// Declared standalone flag
+	pub standalone: Option<bool>,
+}
+
+impl Declaration {
+	/// Declaration of the given version with encoding `utf-8` (and no standalone flag)
+	pub fn new(version: Version) -> Self {
+		Self {
+			version: version.as_str().into(),
+			encoding: Some("utf-8".into()),
+			standalone: None,
+		}
+	}
+
+	/// Declared version (if supported)
+	pub fn xml_version(&self) -> Result<Version> {
+		self.version.parse()
+	}
+}
+
+/// Declaration serializers write by default: version `1.1`, encoding `utf-8`
+impl Default for Declaration {
+	fn default() -> Self {
+		Self::new(Version::V1_1)
+	}
+}
+
+/// Comment or processing instruction outside the root element
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Misc {
+	/// Comment (`<!--text-->`)
+	Comment(String),
+	/// Processing instruction (`<?target data?>`)
+	ProcessingInstruction {
+		/// Target (application) of the instruction
+		target: String,
+		/// Data (might be empty)
+		data: String,
+	},
+}
+
+impl fmt::Display for Misc {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Comment(text) => write!(f, "<!--{}-->", text),
+			Self::ProcessingInstruction { target, data } if data.is_empty() => {
+				write!(f, "<?{}?>", target)
+			},
+			Self::ProcessingInstruction { target, data } => write!(f, "<?{} {}?>", target, data),
+		}
+	}
+}
+
+/// Root element `E` with the prolog (declaration, DOCTYPE, comments and processing instructions
+/// before it) and epilog (comments and processing instructions after it)
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Document<E> {
+	/// XML declaration (none when parsed from a document without declaration)
+	pub declaration: Option<Declaration>,
+	/// Comments and processing instructions before the DOCTYPE (before the root element if there
+	/// is no DOCTYPE)
+	pub prolog: Vec<Misc>,
+	/// Content of the DOCTYPE declaration (`<!DOCTYPE content>`, e.g. `html`), including the
+	/// internal subset
+	pub doctype: Option<String>,
+	/// Comments and processing instructions between DOCTYPE and root element
+	pub after_doctype: Vec<Misc>,
+	/// The root element
+	pub root: E,
+	/// Comments and processing instructions after the root element
+	pub epilog: Vec<Misc>,
+}
+
+impl<E> Document<E> {
+	/// Document with the default declaration and nothing else besides the root element
+	pub fn new(root: E) -> Self {
+		Self {
+			declaration: Some(Declaration::default()),
+			prolog: Vec::new(),
+			doctype: None,
+			after_doctype: Vec::new(),
+			root,
+			epilog: Vec::new(),
+		}
+	}
+
+	/// Replace the root element (keeping everything else)
+	pub fn with_root<F>(self, root: F) -> Document<F> {
+		Document {
+			declaration: self.declaration,
+			prolog: self.prolog,
+			doctype: self.doctype,
+			after_doctype: self.after_doctype,
+			root,
+			epilog: self.epilog,
+		}
+	}
+}
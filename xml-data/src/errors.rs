@@ -1,25 +1,88 @@
 #![allow(missing_docs)] // names should be good enough
-//! Helper functions to generate common errors
+//! Error type and helper functions to generate common errors
 
-use crate::Error;
-use std::fmt;
+use crate::{
+	Name,
+	Position,
+};
+use std::{
+	error::Error as StdError,
+	fmt,
+	io,
+};
 
-pub(crate) enum ParseError {
-	UnexpectedEof { msg: String },
+/// Boxed error from other sources (value conversions, XML backends, user code)
+pub type BoxedError = Box<dyn StdError + Send + Sync + 'static>;
+
+/// Kinds of errors
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+	UnexpectedEof {
+		msg: String,
+	},
 	UnexpectedEnd,
 	UnexpectedDecl,
 	UnexpectedDocType,
 	UnexpectedPI,
 	UnexpectedText,
-	UnexpectedElement { tag: String },
-	UnexpectedAttribute { key: String },
-	InnerElementNotParsed { tag: String },
-	MissingElement { tag: String },
+	/// `expected` lists the tags that would have been accepted (might be empty if unknown)
+	UnexpectedElement {
+		tag: String,
+		expected: Vec<String>,
+	},
+	/// `expected` lists the keys that would have been accepted (might be empty if unknown)
+	UnexpectedAttribute {
+		key: String,
+		expected: Vec<String>,
+	},
+	InnerElementNotParsed {
+		tag: String,
+	},
+	MissingElement {
+		tag: String,
+	},
 	MissingUnknownElement,
-	MissingAttribute { key: String },
+	MissingAttribute {
+		key: String,
+	},
+	InvalidFlagValue {
+		key: String,
+		value: String,
+	},
+	CannotBorrow,
+	/// Prefix of an element or attribute name wasn't declared
+	///
+	/// Unbound prefixes in values (see [`QName::resolve`][crate::QName::resolve]) are reported as
+	/// [`ErrorKind::InvalidValue`] instead.
+	UnboundPrefix {
+		prefix: String,
+	},
+	/// Character can't be written in the document (e.g. control characters in XML 1.0)
+	InvalidCharacter {
+		character: char,
+	},
+	/// Element tag, attribute key or prefix isn't a valid XML name
+	InvalidName {
+		name: String,
+	},
+	/// Attribute written twice for the same element
+	DuplicateAttribute {
+		key: String,
+	},
+	/// Parsing (or serializing) a value failed
+	InvalidValue(BoxedError),
+	/// I/O error in the XML backend
+	Io(io::Error),
+	/// The XML backend failed to parse (or write) XML
+	Syntax(BoxedError),
+	/// Custom error from user code
+	Custom(BoxedError),
+	/// All errors recorded while parsing with error collection enabled
+	Multiple(Vec<Error>),
 }
 
-impl fmt::Debug for ParseError {
+impl fmt::Display for ErrorKind {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
 			Self::UnexpectedEof { msg } => write!(f, "unexpected eof: {}", msg),
@@ -28,70 +91,507 @@ impl fmt::Debug for ParseError {
 			Self::UnexpectedDocType => write!(f, "Unexpected <!DOCTYPE ...>"),
 			Self::UnexpectedPI => write!(f, "Unexpected processing instructions <?...?>"),
 			Self::UnexpectedText => write!(f, "Unexpected (non-whitespace) text/CDATA"),
-			Self::UnexpectedElement { tag } => write!(f, "Unexpected element: {}", tag),
-			Self::UnexpectedAttribute { key } => write!(f, "Unexpected attribute: {}", key),
+			Self::UnexpectedElement { tag, expected } => {
+				write!(f, "Unexpected element: {}", tag)?;
+				fmt_expected(f, tag, expected)
+			},
+			Self::UnexpectedAttribute { key, expected } => {
+				write!(f, "Unexpected attribute: {}", key)?;
+				fmt_expected(f, key, expected)
+			},
 			Self::InnerElementNotParsed { tag } => {
 				write!(f, "Inner element {:?} wasn't fully parsed", tag)
 			},
 			Self::MissingElement { tag } => write!(f, "Missing element {:?}", tag),
 			Self::MissingUnknownElement => write!(f, "Missing element"),
 			Self::MissingAttribute { key } => write!(f, "Missing attribute {:?}", key),
+			Self::InvalidFlagValue { key, value } => {
+				write!(f, "Invalid value {:?} for flag attribute {:?}", value, key)
+			},
+			Self::CannotBorrow => write!(f, "Can't borrow text from input"),
+			Self::UnboundPrefix { prefix } => write!(f, "Unbound namespace prefix {:?}", prefix),
+			Self::InvalidCharacter { character } => {
+				write!(f, "Invalid character U+{:04X}", u32::from(*character))
+			},
+			Self::InvalidName { name } => write!(f, "Invalid name {:?}", name),
+			Self::DuplicateAttribute { key } => write!(f, "Duplicate attribute {:?}", key),
+			Self::InvalidValue(e) => write!(f, "Invalid value: {}", e),
+			Self::Io(e) => write!(f, "I/O error: {}", e),
+			Self::Syntax(e) => write!(f, "Invalid XML: {}", e),
+			Self::Custom(e) => fmt::Display::fmt(e, f),
+			Self::Multiple(errors) => {
+				write!(f, "{} errors", errors.len())?;
+				for e in errors {
+					write!(f, "\n{}", e)?;
+				}
+				Ok(())
+			},
+		}
+	}
+}
+
+impl ErrorKind {
+	/// Close match for an unexpected element tag or attribute key among the expected names
+	pub fn suggestion(&self) -> Option<&str> {
+		match self {
+			Self::UnexpectedElement {
+				tag: name,
+				expected,
+			}
+			| Self::UnexpectedAttribute {
+				key: name,
+				expected,
+			} => suggest(name, expected),
+			_ => None,
+		}
+	}
+}
+
+fn fmt_expected(f: &mut fmt::Formatter<'_>, name: &str, expected: &[String]) -> fmt::Result {
+	if let Some(suggestion) = suggest(name, expected) {
+		write!(f, " (did you mean {:?}?)", suggestion)?;
+	}
+	if let Some((first, rest)) = expected.split_first() {
+		write!(f, "; expected one of: {:?}", first)?;
+		for name in rest {
+			write!(f, ", {:?}", name)?;
+		}
+	}
+	Ok(())
+}
+
+/// Levenshtein distance (in characters)
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut row: Vec<usize> = (0..=b.len()).collect();
+	for (i, ca) in a.chars().enumerate() {
+		let mut diagonal = row[0];
+		row[0] = i + 1;
+		for (j, &cb) in b.iter().enumerate() {
+			let substitute = diagonal + usize::from(ca != cb);
+			diagonal = row[j + 1];
+			row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+		}
+	}
+	row[b.len()]
+}
+
+/// Find the closest expected name if it is close enough to be a typo of `name`
+pub fn suggest<'e, S: AsRef<str>>(name: &str, expected: &'e [S]) -> Option<&'e str> {
+	// allow one edit per three characters (at least one)
+	let max_distance = std::cmp::max(1, name.chars().count() / 3);
+	expected
+		.iter()
+		.map(|candidate| {
+			let candidate = candidate.as_ref();
+			(edit_distance(name, candidate), candidate)
+		})
+		// an exact match isn't a typo (e.g. the namespace differs)
+		.filter(|&(distance, _)| distance > 0 && distance <= max_distance)
+		.min_by_key(|&(distance, _)| distance)
+		.map(|(_, candidate)| candidate)
+}
+
+/// Part of an [`ErrorPath`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+	/// Element with tag; `index` is the 1-based position among siblings with the same tag (not
+	/// set for the root element)
+	Element { tag: String, index: Option<usize> },
+	/// Attribute with key
+	Attribute { key: String },
+}
+
+impl fmt::Display for PathSegment {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Element { tag, index: None } => write!(f, "/{}", tag),
+			Self::Element {
+				tag,
+				index: Some(index),
+			} => write!(f, "/{}[{}]", tag, index),
+			Self::Attribute { key } => write!(f, "/@{}", key),
+		}
+	}
+}
+
+/// Location of an error in the document like `/data/foo[2]/@other`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorPath {
+	// innermost segment first; errors get wrapped while propagating outwards
+	reversed: Vec<PathSegment>,
+}
+
+impl ErrorPath {
+	/// Whether the error location is unknown
+	pub fn is_empty(&self) -> bool {
+		self.reversed.is_empty()
+	}
+
+	/// Segments from the root element to the error location
+	pub fn segments(&self) -> impl Iterator<Item = &PathSegment> + '_ {
+		self.reversed.iter().rev()
+	}
+
+	/// Create path from segments (starting at the root element)
+	pub(crate) fn from_segments(segments: Vec<PathSegment>) -> Self {
+		let mut reversed = segments;
+		reversed.reverse();
+		Self { reversed }
+	}
+}
+
+impl fmt::Display for ErrorPath {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for segment in self.segments() {
+			fmt::Display::fmt(segment, f)?;
+		}
+		Ok(())
+	}
+}
+
+/// Error while parsing or serializing
+///
+/// Use [`Error::kind`] to inspect what went wrong, and [`Error::path`] and [`Error::position`] to
+/// find where it happened.
+pub struct Error {
+	// keep `Result<(), Error>` small
+	inner: Box<ErrorInner>,
+}
+
+struct ErrorInner {
+	kind: ErrorKind,
+	path: ErrorPath,
+	position: Option<Position>,
+}
+
+impl Error {
+	/// Create error from kind
+	pub fn new(kind: ErrorKind) -> Self {
+		Self {
+			inner: Box::new(ErrorInner {
+				kind,
+				path: ErrorPath::default(),
+				position: None,
+			}),
+		}
+	}
+
+	/// Create custom error (from a string or another error)
+	pub fn custom<E: Into<BoxedError>>(error: E) -> Self {
+		Self::new(ErrorKind::Custom(error.into()))
+	}
+
+	/// What went wrong
+	pub fn kind(&self) -> &ErrorKind {
+		&self.inner.kind
+	}
+
+	/// Extract kind
+	pub fn into_kind(self) -> ErrorKind {
+		self.inner.kind
+	}
+
+	/// Where the error happened
+	pub fn path(&self) -> &ErrorPath {
+		&self.inner.path
+	}
+
+	/// Position in the input where the error happened (if the parser tracks positions)
+	pub fn position(&self) -> Option<Position> {
+		self.inner.position
+	}
+
+	/// Set position in the input where the error happened
+	///
+	/// Ignored if the error already has a position.
+	pub fn at(mut self, position: Position) -> Self {
+		if self.inner.position.is_none() {
+			self.inner.position = Some(position);
+		}
+		self
+	}
+
+	/// Whether parsing can continue after this error (when collecting errors)
+	///
+	/// Errors from the XML backend (I/O, syntax, unexpected end of input, unbound namespace
+	/// prefixes in element and attribute names) are fatal.
+	pub fn is_recoverable(&self) -> bool {
+		!matches!(
+			self.inner.kind,
+			ErrorKind::UnexpectedEof { .. }
+				| ErrorKind::Io(_)
+				| ErrorKind::Syntax(_)
+				| ErrorKind::UnboundPrefix { .. }
+				| ErrorKind::Multiple(_)
+		)
+	}
+
+	/// Mark error as happening within the given element
+	///
+	/// Parsers and serializers call this while the error propagates outwards; `index` should be
+	/// the 1-based position among siblings with the same tag (`None` for the root element).
+	pub fn in_element(mut self, tag: &str, index: Option<usize>) -> Self {
+		self.push_element(tag, index);
+		self
+	}
+
+	/// Like [`Error::in_element`] for errors that don't propagate (i.e. collected errors)
+	pub fn push_element(&mut self, tag: &str, index: Option<usize>) {
+		self.inner.path.reversed.push(PathSegment::Element {
+			tag: tag.into(),
+			index,
+		});
+	}
+
+	/// Mark error as happening in the given attribute
+	///
+	/// Ignored if the error already has a location.
+	pub fn in_attribute(mut self, key: &str) -> Self {
+		if self.inner.path.is_empty() {
+			self.inner
+				.path
+				.reversed
+				.push(PathSegment::Attribute { key: key.into() });
 		}
+		self
 	}
 }
 
-impl fmt::Display for ParseError {
+impl fmt::Debug for Error {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		fmt::Debug::fmt(self, f)
+		f.debug_struct("Error")
+			.field("kind", &self.inner.kind)
+			.field("path", &format_args!("{}", self.inner.path))
+			.field("position", &self.inner.position)
+			.finish()
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(position) = &self.inner.position {
+			write!(f, "{}: ", position)?;
+		}
+		if !self.inner.path.is_empty() {
+			write!(f, "{}: ", self.inner.path)?;
+		}
+		fmt::Display::fmt(&self.inner.kind, f)
+	}
+}
+
+impl StdError for Error {
+	fn source(&self) -> Option<&(dyn StdError + 'static)> {
+		match &self.inner.kind {
+			ErrorKind::InvalidValue(e) | ErrorKind::Syntax(e) | ErrorKind::Custom(e) => Some(&**e),
+			ErrorKind::Io(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+impl From<ErrorKind> for Error {
+	fn from(kind: ErrorKind) -> Self {
+		Self::new(kind)
 	}
 }
 
-impl std::error::Error for ParseError {}
+impl From<io::Error> for Error {
+	fn from(e: io::Error) -> Self {
+		io(e)
+	}
+}
 
 pub fn unexpected_eof(msg: &str) -> Error {
-	ParseError::UnexpectedEof { msg: msg.into() }.into()
+	ErrorKind::UnexpectedEof { msg: msg.into() }.into()
 }
 
 pub fn unexpected_end() -> Error {
-	ParseError::UnexpectedEnd.into()
+	ErrorKind::UnexpectedEnd.into()
 }
 
 pub fn unexpected_decl() -> Error {
-	ParseError::UnexpectedDecl.into()
+	ErrorKind::UnexpectedDecl.into()
 }
 
 pub fn unexpected_doctype() -> Error {
-	ParseError::UnexpectedDocType.into()
+	ErrorKind::UnexpectedDocType.into()
 }
 
 pub fn unexpected_pi() -> Error {
-	ParseError::UnexpectedPI.into()
+	ErrorKind::UnexpectedPI.into()
 }
 
 pub fn unexpected_text() -> Error {
-	ParseError::UnexpectedText.into()
+	ErrorKind::UnexpectedText.into()
 }
 
 pub fn unexpected_element(tag: &str) -> Error {
-	ParseError::UnexpectedElement { tag: tag.into() }.into()
+	unexpected_element_expected(tag, &[])
+}
+
+/// Expected names without duplicates
+fn expected_names<N: ToString>(expected: &[N]) -> Vec<String> {
+	let mut names = Vec::<String>::new();
+	for name in expected {
+		let name = name.to_string();
+		if !names.contains(&name) {
+			names.push(name);
+		}
+	}
+	names
+}
+
+pub fn unexpected_element_expected(tag: &str, expected: &[Name<'_>]) -> Error {
+	ErrorKind::UnexpectedElement {
+		tag: tag.into(),
+		expected: expected_names(expected),
+	}
+	.into()
 }
 
 pub fn unexpected_attribute(key: &str) -> Error {
-	ParseError::UnexpectedAttribute { key: key.into() }.into()
+	unexpected_attribute_expected(key, &[])
+}
+
+pub fn unexpected_attribute_expected(key: &str, expected: &[&str]) -> Error {
+	ErrorKind::UnexpectedAttribute {
+		key: key.into(),
+		expected: expected_names(expected),
+	}
+	.into()
 }
 
 pub fn inner_element_not_parsed(tag: &str) -> Error {
-	ParseError::InnerElementNotParsed { tag: tag.into() }.into()
+	ErrorKind::InnerElementNotParsed { tag: tag.into() }.into()
 }
 
 pub fn missing_element(tag: &str) -> Error {
-	ParseError::MissingElement { tag: tag.into() }.into()
+	ErrorKind::MissingElement { tag: tag.into() }.into()
 }
 
 pub fn missing_unknown_element() -> Error {
-	ParseError::MissingUnknownElement.into()
+	ErrorKind::MissingUnknownElement.into()
 }
 
 pub fn missing_attribute(key: &str) -> Error {
-	ParseError::MissingAttribute { key: key.into() }.into()
+	ErrorKind::MissingAttribute { key: key.into() }.into()
+}
+
+pub fn invalid_flag_value(key: &str, value: &str) -> Error {
+	ErrorKind::InvalidFlagValue {
+		key: key.into(),
+		value: value.into(),
+	}
+	.into()
+}
+
+pub fn cannot_borrow() -> Error {
+	ErrorKind::CannotBorrow.into()
+}
+
+pub fn unbound_prefix(prefix: &str) -> Error {
+	ErrorKind::UnboundPrefix {
+		prefix: prefix.into(),
+	}
+	.into()
+}
+
+pub fn invalid_character(character: char) -> Error {
+	ErrorKind::InvalidCharacter { character }.into()
+}
+
+pub fn invalid_name(name: &str) -> Error {
+	ErrorKind::InvalidName { name: name.into() }.into()
+}
+
+pub fn duplicate_attribute(key: &str) -> Error {
+	ErrorKind::DuplicateAttribute { key: key.into() }.into()
+}
+
+pub fn invalid_value<E: Into<BoxedError>>(error: E) -> Error {
+	ErrorKind::InvalidValue(error.into()).into()
+}
+
+pub fn io(error: io::Error) -> Error {
+	ErrorKind::Io(error).into()
+}
+
+pub fn syntax<E: Into<BoxedError>>(error: E) -> Error {
+	ErrorKind::Syntax(error.into()).into()
+}
+
+pub fn custom<E: Into<BoxedError>>(error: E) -> Error {
+	Error::custom(error)
+}
+
+pub fn multiple(errors: Vec<Error>) -> Error {
+	ErrorKind::Multiple(errors).into()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+	#[test]
+	fn test_send_sync() {
+		assert_send_sync::<Error>();
+	}
+
+	#[test]
+	fn test_source() {
+		let e = invalid_value("x".parse::<u32>().unwrap_err());
+		assert!(matches!(e.kind(), ErrorKind::InvalidValue(_)));
+		assert!(e.source().is_some());
+		assert!(missing_attribute("key").source().is_none());
+	}
+
+	#[test]
+	fn test_suggest() {
+		assert_eq!(edit_distance("adress", "address"), 1);
+		assert_eq!(edit_distance("", "abc"), 3);
+		assert_eq!(edit_distance("kitten", "sitting"), 3);
+		assert_eq!(suggest("adress", &["name", "address"]), Some("address"));
+		assert_eq!(suggest("foo", &["name", "address"]), None);
+		assert_eq!(suggest("name", &["name"]), None);
+
+		let e = unexpected_element_expected("adress", &[Name::from("name"), Name::from("address")]);
+		assert_eq!(e.kind().suggestion(), Some("address"));
+		assert_eq!(
+			e.to_string(),
+			r#"Unexpected element: adress (did you mean "address"?); expected one of: "name", "address""#
+		);
+		assert_eq!(
+			unexpected_attribute("x").to_string(),
+			"Unexpected attribute: x"
+		);
+	}
+
+	#[test]
+	fn test_path() {
+		let e = invalid_value("x".parse::<u32>().unwrap_err())
+			.in_attribute("other")
+			.in_attribute("ignored")
+			.in_element("foo", Some(2))
+			.in_element("data", None);
+		assert_eq!(e.path().to_string(), "/data/foo[2]/@other");
+		assert_eq!(
+			e.to_string(),
+			"/data/foo[2]/@other: Invalid value: invalid digit found in string"
+		);
+
+		let position = Position {
+			offset: 60,
+			line: 12,
+			column: 5,
+		};
+		let e = missing_attribute("key").at(position).at(Position::START);
+		assert_eq!(e.position(), Some(position));
+		assert_eq!(
+			e.to_string(),
+			r#"line 12, column 5: Missing attribute "key""#
+		);
+	}
 }
@@ -1,5 +1,6 @@
 #![cfg_attr(doc_cfg, feature(doc_cfg))]
 #![warn(missing_docs)]
+#![forbid(unsafe_code)]
 #![doc(html_root_url = "https://docs.rs/xml-data/0.0.1")]
 //! This library provides a generic interface to parse XML data: a user might implement how to
 //! parse and serialize their data (possibly derived), while others will implement adaptors for
@@ -19,6 +20,18 @@
 //! named features:
 //! - [`quick-xml`](https://crates.io/crates/quick-xml)
 //!
+//! The `tokio` feature adds async adaptors (built on the `quick-xml` adaptors) for
+//! [`tokio`](https://crates.io/crates/tokio).
+//!
+//! Parsing and serializing is namespace-aware: element and attribute names are passed as
+//! [`Name`] (namespace and local name); see [`namespace`].
+//!
+//! [`Document`] keeps the declaration, DOCTYPE, comments and processing instructions around the
+//! root element.
+//!
+//! Errors can be rendered against the source document using [`report::Report`]; with the `miette`
+//! feature they also implement [`miette::Diagnostic`](https://docs.rs/miette).
+//!
 //! If the `derive` feature is enabled the following traits can be derived:
 //! - `Element`
 //! - `parser::Element`
@@ -27,14 +40,33 @@
 //! - `parser::Inner`
 //! - `serializer::Inner`
 
+pub mod document;
 pub mod errors;
 pub mod extensions;
+pub mod namespace;
 pub mod parser;
+mod position;
+pub mod report;
 pub mod serializer;
 mod traits;
 
-/// For now we use a simple boxed error to show the user
-pub type Error = Box<dyn std::error::Error>;
+pub use self::{
+	document::Document,
+	errors::{
+		Error,
+		ErrorKind,
+	},
+	namespace::{
+		Name,
+		QName,
+	},
+	position::{
+		Position,
+		Span,
+		Spanned,
+	},
+};
+
 /// Result alias with out error type included
 pub type Result<T> = std::result::Result<T, Error>;
 
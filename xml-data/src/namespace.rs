@@ -0,0 +1,346 @@
+//! XML namespaces
+//!
+//! Parsers resolve prefixes of element and attribute names and pass the [`Name`] (namespace and
+//! local name) to the parse states; `xmlns` declarations are not passed as attributes.  Serializers
+//! declare namespaces and choose prefixes.
+//!
+//! Values can contain prefixed names too (e.g. `xsi:type="tns:Order"`); [`QName`] resolves them
+//! with the declarations in scope while parsing (see [`ParseContext::resolve_prefix`]) and
+//! serializers declare prefixes for them (see
+//! [`Serializer::qualified_name`][crate::serializer::Serializer::qualified_name]).
+
+use crate::{
+	errors,
+	parser::ParseContext,
+	Result,
+};
+use std::{
+	fmt,
+	sync::Arc,
+};
+
+/// Namespace bound to the `xml` prefix
+pub const XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Namespace bound to the `xmlns` prefix
+pub const XMLNS: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Expanded name of an element or attribute: namespace and local name (without prefix)
+///
+/// Unprefixed element names are in the default namespace (if one was declared); unprefixed
+/// attribute names have no namespace.
+///
+/// Displays as `{namespace}local` (or just `local` without namespace).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Name<'n> {
+	/// Namespace (`None` if the name isn't in a namespace)
+	pub namespace: Option<&'n str>,
+	/// Local name
+	pub local: &'n str,
+}
+
+impl<'n> Name<'n> {
+	/// Create name from namespace and local name
+	pub const fn new(namespace: Option<&'n str>, local: &'n str) -> Self {
+		Self { namespace, local }
+	}
+
+	/// Create name without namespace
+	pub const fn unqualified(local: &'n str) -> Self {
+		Self {
+			namespace: None,
+			local,
+		}
+	}
+}
+
+impl<'n> From<&'n str> for Name<'n> {
+	fn from(local: &'n str) -> Self {
+		Self::unqualified(local)
+	}
+}
+
+impl fmt::Display for Name<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(namespace) = self.namespace {
+			write!(f, "{{{}}}", namespace)?;
+		}
+		f.write_str(self.local)
+	}
+}
+
+/// Owned expanded name, e.g. as attribute or text value (`xsi:type="tns:Order"`)
+///
+/// Values are prefixed names in the document; parse them with
+/// [`parser::ValueQName`][crate::parser::ValueQName] and serialize them with
+/// [`serializer::ValueQName`][crate::serializer::ValueQName] (the derive macros do this for fields
+/// of type `QName`).
+///
+/// Displays as `{namespace}local` like [`Name`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QName {
+	/// Namespace (`None` if the name isn't in a namespace)
+	pub namespace: Option<String>,
+	/// Local name
+	pub local: String,
+}
+
+impl QName {
+	/// Create name from namespace and local name
+	pub fn new(namespace: Option<&str>, local: &str) -> Self {
+		Self {
+			namespace: namespace.map(String::from),
+			local: local.into(),
+		}
+	}
+
+	/// Borrow as [`Name`]
+	pub fn name(&self) -> Name<'_> {
+		Name::new(self.namespace.as_deref(), &self.local)
+	}
+
+	/// Resolve prefixed name (`prefix:local`) with the declarations in scope of `context` (see
+	/// [`ParseContext::resolve_prefix`])
+	///
+	/// Unprefixed names are in the default namespace (if one is declared); surrounding whitespace
+	/// is ignored.  An unbound prefix is an invalid value (not a fatal error like unbound prefixes
+	/// in element and attribute names).
+	pub fn resolve(qname: &str, context: &dyn ParseContext) -> Result<Self> {
+		let qname = qname.trim();
+		let (prefix, local) = split_qname(qname);
+		if !is_ncname(local) || !prefix.is_none_or(is_ncname) {
+			return Err(errors::invalid_value(format!(
+				"invalid qualified name: {:?}",
+				qname
+			)));
+		}
+		let namespace = match prefix {
+			Some(prefix) => Some(context.resolve_prefix(prefix).ok_or_else(|| {
+				errors::invalid_value(format!(
+					"unbound namespace prefix {:?} in qualified name {:?}",
+					prefix, qname
+				))
+			})?),
+			None => context.resolve_prefix(""),
+		};
+		Ok(Self::new(namespace, local))
+	}
+}
+
+impl From<Name<'_>> for QName {
+	fn from(name: Name<'_>) -> Self {
+		Self::new(name.namespace, name.local)
+	}
+}
+
+impl fmt::Display for QName {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(&self.name(), f)
+	}
+}
+
+fn is_name_start_char(c: char) -> bool {
+	matches!(c,
+		':' | 'A'..='Z' | '_' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}'
+		| '\u{F8}'..='\u{2FF}' | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}'
+		| '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}'
+		| '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}'
+		| '\u{10000}'..='\u{EFFFF}')
+}
+
+fn is_name_char(c: char) -> bool {
+	is_name_start_char(c)
+		|| matches!(c, '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+/// Whether `name` matches the `Name` production of XML (e.g. processing instruction targets)
+pub fn is_name(name: &str) -> bool {
+	let mut chars = name.chars();
+	chars.next().is_some_and(is_name_start_char) && chars.all(is_name_char)
+}
+
+/// Whether `name` is a name without colon (`NCName` of "Namespaces in XML"), as required for
+/// prefixes, element tags and local names of attributes
+pub fn is_ncname(name: &str) -> bool {
+	is_name(name) && !name.contains(':')
+}
+
+/// Fail unless `name` is a name without colon (see [`is_ncname`])
+pub(crate) fn check_ncname(name: &str) -> Result<()> {
+	if is_ncname(name) {
+		Ok(())
+	} else {
+		Err(errors::invalid_name(name))
+	}
+}
+
+/// Split qualified name into prefix and local name
+pub(crate) fn split_qname(qname: &str) -> (Option<&str>, &str) {
+	match qname.split_once(':') {
+		Some((prefix, local)) => (Some(prefix), local),
+		None => (None, qname),
+	}
+}
+
+/// Namespace declarations in scope
+///
+/// The default namespace is bound to the empty prefix; binding a prefix to the empty namespace
+/// undeclares it.
+#[derive(Debug, Default)]
+pub(crate) struct Namespaces {
+	bindings: Vec<(String, Arc<str>)>,
+	// number of bindings when each open scope was entered
+	scopes: Vec<usize>,
+}
+
+impl Namespaces {
+	pub(crate) fn push_scope(&mut self) {
+		self.scopes.push(self.bindings.len());
+	}
+
+	pub(crate) fn pop_scope(&mut self) {
+		if let Some(len) = self.scopes.pop() {
+			self.bindings.truncate(len);
+		}
+	}
+
+	/// Bind prefix in the current scope
+	pub(crate) fn declare(&mut self, prefix: &str, namespace: &str) {
+		self.bindings.push((prefix.into(), namespace.into()));
+	}
+
+	/// Whether prefix was bound in the current scope
+	pub(crate) fn declared_in_scope(&self, prefix: &str) -> bool {
+		let start = self.scopes.last().copied().unwrap_or(0);
+		self.bindings[start..].iter().any(|(p, _)| p == prefix)
+	}
+
+	/// Namespace bound to prefix (the empty prefix for the default namespace)
+	pub(crate) fn resolve(&self, prefix: &str) -> Option<Arc<str>> {
+		match prefix {
+			"xml" => Some(XML.into()),
+			"xmlns" => Some(XMLNS.into()),
+			_ => self.binding(prefix).cloned(),
+		}
+	}
+
+	/// Namespace bound to prefix by the innermost declaration (unless it undeclares the prefix)
+	fn binding(&self, prefix: &str) -> Option<&Arc<str>> {
+		let (_, namespace) = self.bindings.iter().rev().find(|(p, _)| p == prefix)?;
+		Some(namespace).filter(|namespace| !namespace.is_empty())
+	}
+
+	/// Prefix currently bound to namespace (the empty prefix only if `allow_default`)
+	pub(crate) fn prefix(&self, namespace: &str, allow_default: bool) -> Option<&str> {
+		if namespace == XML {
+			return Some("xml");
+		}
+		self.bindings
+			.iter()
+			.enumerate()
+			.rev()
+			.find(|&(ndx, (prefix, ns))| {
+				&**ns == namespace
+					&& (allow_default || !prefix.is_empty())
+					// not shadowed by a later binding
+					&& !self.bindings[ndx + 1..].iter().any(|(p, _)| p == prefix)
+			})
+			.map(|(_, (prefix, _))| prefix.as_str())
+	}
+}
+
+impl ParseContext for Namespaces {
+	fn resolve_prefix(&self, prefix: &str) -> Option<&str> {
+		match prefix {
+			"xml" => Some(XML),
+			"xmlns" => Some(XMLNS),
+			_ => self.binding(prefix).map(|namespace| &**namespace),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_scopes() {
+		let mut ns = Namespaces::default();
+		ns.push_scope();
+		ns.declare("", "urn:a");
+		ns.declare("b", "urn:b");
+		assert_eq!(ns.resolve("").as_deref(), Some("urn:a"));
+		assert_eq!(ns.resolve("xml").as_deref(), Some(XML));
+		assert_eq!(ns.resolve("c"), None);
+		assert_eq!(ns.prefix("urn:a", true), Some(""));
+		assert_eq!(ns.prefix("urn:a", false), None);
+
+		ns.push_scope();
+		ns.declare("", "");
+		ns.declare("b", "urn:c");
+		assert!(ns.declared_in_scope("b"));
+		assert!(!ns.declared_in_scope("c"));
+		assert_eq!(ns.resolve(""), None);
+		assert_eq!(ns.prefix("urn:b", false), None);
+		assert_eq!(ns.prefix("urn:c", false), Some("b"));
+		ns.pop_scope();
+
+		assert_eq!(ns.resolve("b").as_deref(), Some("urn:b"));
+		assert_eq!(ns.prefix(XML, false), Some("xml"));
+	}
+
+	#[test]
+	fn test_name() {
+		assert_eq!(Name::new(Some("urn:a"), "x").to_string(), "{urn:a}x");
+		assert_eq!(Name::from("x").to_string(), "x");
+		assert_eq!(split_qname("a:x"), (Some("a"), "x"));
+		assert!(is_name("a:b-1.\u{B7}"));
+		assert!(is_name("\u{E9}t\u{E9}"));
+		assert!(!is_name(""));
+		assert!(!is_name("1a"));
+		assert!(!is_name("-a"));
+		assert!(!is_name("a b"));
+		assert!(!is_name("a<"));
+		assert!(is_ncname("_x"));
+		assert!(!is_ncname("a:b"));
+		assert_eq!(
+			check_ncname("a b").unwrap_err().to_string(),
+			"Invalid name \"a b\""
+		);
+	}
+
+	#[test]
+	fn test_qname() {
+		let mut ns = Namespaces::default();
+		ns.push_scope();
+		ns.declare("", "urn:d");
+		ns.declare("a", "urn:a");
+		assert_eq!(
+			QName::resolve(" a:x ", &ns).unwrap(),
+			QName::new(Some("urn:a"), "x")
+		);
+		assert_eq!(QName::resolve("x", &ns).unwrap().to_string(), "{urn:d}x");
+		assert_eq!(
+			QName::resolve("xml:lang", &ns).unwrap(),
+			QName::new(Some(XML), "lang")
+		);
+		assert_eq!(
+			QName::resolve("b:x", &ns).unwrap_err().to_string(),
+			"Invalid value: unbound namespace prefix \"b\" in qualified name \"b:x\""
+		);
+		assert!(QName::resolve("a:", &ns).is_err());
+		assert!(QName::resolve(":x", &ns).is_err());
+		assert!(QName::resolve("a:1x", &ns).is_err());
+		ns.pop_scope();
+		assert_eq!(ns.resolve_prefix("a"), None);
+
+		// without declarations (e.g. parsers not tracking them)
+		struct NoNamespaces;
+		impl ParseContext for NoNamespaces {}
+		assert_eq!(
+			QName::resolve("x", &NoNamespaces).unwrap(),
+			QName::from(Name::from("x"))
+		);
+		assert!(QName::resolve("a:x", &NoNamespaces).is_err());
+	}
+}
@@ -1,9 +1,51 @@
 use crate::{
 	errors,
+	parser::WarningKind,
+	Name,
+	Position,
 	Result,
+	Span,
 };
 use std::borrow::Cow;
 
+/// Processing instruction (`<?target data?>`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessingInstruction<'a> {
+	/// Target (application) of the instruction
+	pub target: Cow<'a, str>,
+	/// Data (without the whitespace after the target; might be empty)
+	pub data: Cow<'a, str>,
+}
+
+impl<'a> ProcessingInstruction<'a> {
+	/// Split content (everything between `<?` and `?>`) into target and data
+	pub(crate) fn from_content(content: Cow<'a, str>) -> Self {
+		fn split(content: &str) -> (&str, &str) {
+			match content.find(|c: char| c.is_ascii_whitespace()) {
+				Some(end) => (&content[..end], content[end..].trim_start()),
+				None => (content, ""),
+			}
+		}
+
+		match content {
+			Cow::Borrowed(content) => {
+				let (target, data) = split(content);
+				Self {
+					target: Cow::Borrowed(target),
+					data: Cow::Borrowed(data),
+				}
+			},
+			Cow::Owned(content) => {
+				let (target, data) = split(&content);
+				Self {
+					target: Cow::Owned(target.into()),
+					data: Cow::Owned(data.into()),
+				}
+			},
+		}
+	}
+}
+
 /// A state to parse exactly one element
 ///
 /// The idea is that a parser will try different implementors of this to parse an element it finds;
@@ -12,40 +54,95 @@ use std::borrow::Cow;
 ///
 /// After a successful [`ElementState::parse_element_start`] the parser needs to call
 /// [`ElementState::parse_element_attribute`] for all attributes on the element, then
-/// [`ElementState::parse_element_inner_text`] and [`ElementState::parse_element_inner_node`] until
+/// [`ElementState::parse_element_inner_text`] and [`ElementState::parse_element_inner_node`] (and
+/// [`ElementState::parse_element_inner_comment`] and [`ElementState::parse_element_inner_pi`]) until
 /// the closing tag of the element is hit, upon which it needs to call
 /// [`ElementState::parse_element_finish`].
-pub trait ElementState: Sized {
+///
+/// `'a` is the lifetime of the input data; parsers reading from memory can pass text and
+/// attribute values borrowed from the input as `Cow::Borrowed`.  Parsers that can't borrow from
+/// their input pass owned data and therefore work with any lifetime.
+///
+/// Element and attribute names are passed with resolved namespaces (see [`Name`]); namespace
+/// declarations are not passed as attributes.
+pub trait ElementState<'a>: Sized {
 	/// Once fully parsed this is the resulting output type.
 	type Output: Sized;
 
-	/// Try creating state to parse an element with the passed `tag`.
-	fn parse_element_start(tag: &str) -> Option<Self>;
+	/// Try creating state to parse an element with the passed name.
+	fn parse_element_start(name: Name<'_>) -> Option<Self>;
+
+	/// Add names accepted by [`ElementState::parse_element_start`] (for error messages)
+	///
+	/// The default implementation doesn't add any (i.e. the accepted tags are unknown).
+	fn expected_tags(tags: &mut Vec<Name<'static>>) {
+		let _ = tags;
+	}
 
 	/// Parse attribute into state
 	///
+	/// The parser adds the attribute to the path of returned errors.
+	///
 	/// The default implementation will fail with "unexpected attribute".
-	fn parse_element_attribute(&mut self, key: &str, value: Cow<'_, str>) -> Result<()> {
-		let _ = value;
-		Err(errors::unexpected_attribute(key))
+	fn parse_element_attribute(
+		&mut self,
+		name: Name<'_>,
+		value: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		let _ = (value, context);
+		Err(errors::unexpected_attribute(&name.to_string()))
 	}
 
 	/// Parse text or CDATA into state.
 	///
 	/// The default implementation will ignore whitespace and fail otherwise.
-	fn parse_element_inner_text(&mut self, text: Cow<'_, str>) -> Result<()> {
+	fn parse_element_inner_text(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		let _ = context;
 		if !text.trim().is_empty() {
 			return Err(errors::unexpected_text());
 		}
 		Ok(())
 	}
 
+	/// Parse comment into state.
+	///
+	/// The default implementation will ignore it.
+	fn parse_element_inner_comment(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		let _ = (text, context);
+		Ok(())
+	}
+
+	/// Parse processing instruction into state.
+	///
+	/// The default implementation will fail with "unexpected processing instruction".
+	fn parse_element_inner_pi(
+		&mut self,
+		pi: ProcessingInstruction<'a>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		let _ = (pi, context);
+		Err(errors::unexpected_pi())
+	}
+
 	/// Parse inner elements.
 	///
 	/// The default implementation will fail with "unexpected element".
-	fn parse_element_inner_node<P: ElementParser>(&mut self, tag: &str, parser: P) -> Result<()> {
+	fn parse_element_inner_node<P: ElementParser<'a>>(
+		&mut self,
+		name: Name<'_>,
+		parser: P,
+	) -> Result<()> {
 		let _ = parser;
-		Err(errors::unexpected_element(tag))
+		Err(errors::unexpected_element(&name.to_string()))
 	}
 
 	/// Finish parsing an element.
@@ -54,6 +151,15 @@ pub trait ElementState: Sized {
 	/// optionally check data for consistency.
 	fn parse_element_finish(self) -> Result<Self::Output>;
 
+	/// Span of the element in the input (from the start of the start tag to the end of the end
+	/// tag)
+	///
+	/// Parsers that track positions call this before returning from
+	/// [`ElementParser::parse_element_state`]; the default implementation ignores it.
+	fn parse_element_span(&mut self, span: Span) {
+		let _ = span;
+	}
+
 	/// In case [`ElementState::parse_element_start`] didn't get to accept any element (either
 	/// because it always returned `None` or there just wasn't enough data), a parser can use this
 	/// to generate an error.
@@ -64,8 +170,38 @@ pub trait ElementState: Sized {
 	}
 }
 
+/// Context of the element currently parsed
+///
+/// Parsers pass it to [`ElementState::parse_element_attribute`] and
+/// [`ElementState::parse_element_inner_text`]; the [`ElementParser`] passed to
+/// [`ElementState::parse_element_inner_node`] provides it too.
+pub trait ParseContext {
+	/// Record a warning for the element currently parsed (states should do this when they ignore
+	/// data)
+	///
+	/// Parsers supporting warnings (like the `quick-xml` adaptors) attach the path and position; for
+	/// [`WarningKind::IgnoredAttribute`] the path includes the attribute.  The default
+	/// implementation discards the warning.
+	fn warn(&mut self, kind: WarningKind) {
+		let _ = kind;
+	}
+
+	/// Namespace bound to prefix (the empty prefix for the default namespace) in scope of the
+	/// element currently parsed
+	///
+	/// This is meant for [`Value`][super::Value] implementations parsing prefixed names (like
+	/// [`QName::resolve`][crate::QName::resolve]).  The default implementation returns `None`
+	/// (parser doesn't track namespace declarations).
+	fn resolve_prefix(&self, prefix: &str) -> Option<&str> {
+		let _ = prefix;
+		None
+	}
+}
+
 /// A parser that is ready to parse exactly one element (and nested data).
-pub trait ElementParser: Sized {
+///
+/// `'a` is the lifetime of data the parser can borrow from its input.
+pub trait ElementParser<'a>: ParseContext + Sized {
 	/// Start parsing an element with the prepared state
 	///
 	/// A parser will call the various [`ElementState`] to parse the element.
@@ -73,5 +209,22 @@ pub trait ElementParser: Sized {
 	/// Users of this method will create the state using [`ElementState::parse_element_start`] and
 	/// produce the final result using [`ElementState::parse_element_finish`] after calling this
 	/// method.
-	fn parse_element_state<E: ElementState>(self, state: &mut E) -> Result<()>;
+	fn parse_element_state<E: ElementState<'a>>(self, state: &mut E) -> Result<()>;
+
+	/// Like [`ElementParser::parse_element_state`], but on a recoverable error (see
+	/// [`Error::is_recoverable`][`crate::Error::is_recoverable`]) skip the rest of the element
+	/// before returning the error, so the caller can continue with the next element.
+	///
+	/// The default implementation doesn't skip anything (parsing will likely fail later on with
+	/// "inner element not parsed").
+	fn parse_element_state_or_skip<E: ElementState<'a>>(self, state: &mut E) -> Result<()> {
+		self.parse_element_state(state)
+	}
+
+	/// Position of the start tag of the element in the input
+	///
+	/// The default implementation returns `None` (parser doesn't track positions).
+	fn position(&self) -> Option<Position> {
+		None
+	}
 }
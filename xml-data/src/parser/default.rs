@@ -6,21 +6,25 @@ use crate::parser::{
 /// Parsable element
 ///
 /// This links the (default) state type used to parse this element.
-pub trait Element: Sized {
+///
+/// `'a` is the lifetime of the input data the element can borrow from.
+pub trait Element<'a>: Sized {
 	/// Parse state to use for this element
-	type ParseState: ElementState<Output = Self>;
+	type ParseState: ElementState<'a, Output = Self>;
 }
 
 /// Type alias to find the default parse state for an `Element`
-pub type ElementDefaultParseState<E> = <E as Element>::ParseState;
+pub type ElementDefaultParseState<'a, E> = <E as Element<'a>>::ParseState;
 
 /// Parsable inner data (multiple elements)
 ///
 /// This links the (default) state type used to parse this.
-pub trait Inner: Sized {
+///
+/// `'a` is the lifetime of the input data the inner data can borrow from.
+pub trait Inner<'a>: Sized {
 	/// Parse state to use for this inner data
-	type ParseState: InnerState<Output = Self>;
+	type ParseState: InnerState<'a, Output = Self>;
 }
 
 /// Type alias to find the default parse state for an `Inner`
-pub type InnerDefaultParseState<I> = <I as Inner>::ParseState;
+pub type InnerDefaultParseState<'a, I> = <I as Inner<'a>>::ParseState;
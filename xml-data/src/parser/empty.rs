@@ -0,0 +1,23 @@
+use crate::{
+	parser::ElementState,
+	Name,
+	Result,
+};
+
+/// Can be used as `ElementState` to parse an element without any content (attributes, sub elements
+/// or non-whitespace text)
+///
+/// Accepts any name; the caller is responsible for checking it.
+pub struct EmptyElement;
+
+impl<'a> ElementState<'a> for EmptyElement {
+	type Output = ();
+
+	fn parse_element_start(_name: Name<'_>) -> Option<Self> {
+		Some(Self)
+	}
+
+	fn parse_element_finish(self) -> Result<Self::Output> {
+		Ok(())
+	}
+}
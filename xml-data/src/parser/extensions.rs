@@ -4,23 +4,31 @@ use crate::{
 		ElementParser,
 		ElementState,
 	},
+	Name,
 	Result,
 };
 
 /// extend `ElementParser` trait with convenience methods
-pub trait ElementParserExt: ElementParser {
+pub trait ElementParserExt<'a>: ElementParser<'a> {
 	/// Full parsing of an element (fails hard if the tag doesn't work out)
 	///
 	/// If you need to handle `parse_element_start` failures (e.g. by trying a different state) you
 	/// can't use this method.
-	fn parse_element<E: ElementState>(self, tag: &str) -> Result<E::Output> {
-		let mut state = match E::parse_element_start(tag) {
+	fn parse_element<E: ElementState<'a>>(self, name: Name<'_>) -> Result<E::Output> {
+		let mut state = match E::parse_element_start(name) {
 			Some(s) => s,
-			None => return Err(errors::unexpected_element(tag)),
+			None => {
+				let mut expected = Vec::new();
+				E::expected_tags(&mut expected);
+				return Err(errors::unexpected_element_expected(
+					&name.to_string(),
+					&expected,
+				));
+			},
 		};
 		self.parse_element_state(&mut state)?;
 		state.parse_element_finish()
 	}
 }
 
-impl<P: ElementParser> ElementParserExt for P {}
+impl<'a, P: ElementParser<'a>> ElementParserExt<'a> for P {}
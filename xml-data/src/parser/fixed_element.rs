@@ -3,71 +3,147 @@ use crate::{
 	parser::{
 		ElementParser,
 		ElementState,
+		ParseContext,
+		ProcessingInstruction,
 	},
+	Name,
 	Result,
 };
 use std::borrow::Cow;
 
-/// Convenience trait to implement instead of `ElementState` if your element has a fixed tag.
-pub trait FixedElementState: Default {
+/// Convenience trait to implement instead of `ElementState` if your element has a fixed tag
+/// (and namespace).
+pub trait FixedElementState<'a>: Default {
 	/// Same as `ElementState::Output`
 	type Output: Sized;
 
-	/// Fixed tag
+	/// Fixed tag (local name)
 	const TAG: &'static str;
 
+	/// Fixed namespace (none by default)
+	const NAMESPACE: Option<&'static str> = None;
+
 	/// Same as `ElementState::parse_element_attribute`
-	fn parse_element_attribute(&mut self, key: &str, value: Cow<'_, str>) -> Result<()> {
-		let _ = value;
-		Err(errors::unexpected_attribute(key))
+	fn parse_element_attribute(
+		&mut self,
+		name: Name<'_>,
+		value: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		let _ = (value, context);
+		Err(errors::unexpected_attribute(&name.to_string()))
 	}
 
 	/// Same as `ElementState::parse_element_inner_text`
-	fn parse_element_inner_text(&mut self, text: Cow<'_, str>) -> Result<()> {
+	fn parse_element_inner_text(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		let _ = context;
 		if !text.trim().is_empty() {
 			return Err(errors::unexpected_text());
 		}
 		Ok(())
 	}
 
+	/// Same as `ElementState::parse_element_inner_comment`
+	fn parse_element_inner_comment(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		let _ = (text, context);
+		Ok(())
+	}
+
+	/// Same as `ElementState::parse_element_inner_pi`
+	fn parse_element_inner_pi(
+		&mut self,
+		pi: ProcessingInstruction<'a>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		let _ = (pi, context);
+		Err(errors::unexpected_pi())
+	}
+
 	/// Same as `ElementState::parse_element_inner_node`
-	fn parse_element_inner_node<P: ElementParser>(&mut self, tag: &str, parser: P) -> Result<()> {
+	fn parse_element_inner_node<P: ElementParser<'a>>(
+		&mut self,
+		name: Name<'_>,
+		parser: P,
+	) -> Result<()> {
 		let _ = parser;
-		Err(errors::unexpected_element(tag))
+		Err(errors::unexpected_element(&name.to_string()))
 	}
 
 	/// Same as `ElementState::parse_element_finish`
 	fn parse_element_finish(self) -> Result<Self::Output>;
 }
 
-impl<E: FixedElementState> ElementState for E {
-	type Output = <E as FixedElementState>::Output;
+impl<'a, E: FixedElementState<'a>> ElementState<'a> for E {
+	type Output = <E as FixedElementState<'a>>::Output;
 
-	fn parse_element_start(tag: &str) -> Option<Self> {
-		if tag == Self::TAG {
+	fn parse_element_start(name: Name<'_>) -> Option<Self> {
+		if name == Name::new(Self::NAMESPACE, Self::TAG) {
 			Some(E::default())
 		} else {
 			None
 		}
 	}
 
-	fn parse_element_attribute(&mut self, key: &str, value: Cow<'_, str>) -> Result<()> {
-		<E as FixedElementState>::parse_element_attribute(self, key, value)
+	fn expected_tags(tags: &mut Vec<Name<'static>>) {
+		tags.push(Name::new(Self::NAMESPACE, Self::TAG));
+	}
+
+	fn parse_element_attribute(
+		&mut self,
+		name: Name<'_>,
+		value: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		<E as FixedElementState<'a>>::parse_element_attribute(self, name, value, context)
+	}
+
+	fn parse_element_inner_text(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		<E as FixedElementState<'a>>::parse_element_inner_text(self, text, context)
+	}
+
+	fn parse_element_inner_comment(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		<E as FixedElementState<'a>>::parse_element_inner_comment(self, text, context)
 	}
 
-	fn parse_element_inner_text(&mut self, text: Cow<'_, str>) -> Result<()> {
-		<E as FixedElementState>::parse_element_inner_text(self, text)
+	fn parse_element_inner_pi(
+		&mut self,
+		pi: ProcessingInstruction<'a>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		<E as FixedElementState<'a>>::parse_element_inner_pi(self, pi, context)
 	}
 
-	fn parse_element_inner_node<P: ElementParser>(&mut self, tag: &str, parser: P) -> Result<()> {
-		<E as FixedElementState>::parse_element_inner_node(self, tag, parser)
+	fn parse_element_inner_node<P: ElementParser<'a>>(
+		&mut self,
+		name: Name<'_>,
+		parser: P,
+	) -> Result<()> {
+		<E as FixedElementState<'a>>::parse_element_inner_node(self, name, parser)
 	}
 
 	fn parse_element_finish(self) -> Result<Self::Output> {
-		<E as FixedElementState>::parse_element_finish(self)
+		<E as FixedElementState<'a>>::parse_element_finish(self)
 	}
 
 	fn parse_error_not_found<T>() -> Result<T> {
-		Err(errors::missing_element(Self::TAG))
+		Err(errors::missing_element(
+			&Name::new(Self::NAMESPACE, Self::TAG).to_string(),
+		))
 	}
 }
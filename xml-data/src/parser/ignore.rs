@@ -2,31 +2,69 @@ use crate::{
 	parser::{
 		ElementParser,
 		ElementState,
+		ParseContext,
+		ProcessingInstruction,
+		WarningKind,
 	},
+	Name,
 	Result,
 };
 use std::borrow::Cow;
 
-/// Can be used as `ElementState` to ignore an element with all content (attributes and sub
-/// elements and text)
+/// Can be used as `ElementState` to ignore an element with all content (attributes, sub elements,
+/// text, comments and processing instructions)
+///
+/// [`IgnoreElement::ignore`] records a [`WarningKind::IgnoredElement`] warning (see
+/// [`ParseContext::warn`]); the content of the element doesn't produce further warnings.
 pub struct IgnoreElement;
 
-impl ElementState for IgnoreElement {
+impl IgnoreElement {
+	/// Ignore element with `name` (recording a warning)
+	pub fn ignore<'a, P: ElementParser<'a>>(name: Name<'_>, mut parser: P) -> Result<()> {
+		parser.warn(WarningKind::IgnoredElement {
+			tag: name.to_string(),
+		});
+		parser.parse_element_state(&mut Self)
+	}
+}
+
+impl<'a> ElementState<'a> for IgnoreElement {
 	type Output = ();
 
-	fn parse_element_start(_tag: &str) -> Option<Self> {
+	fn parse_element_start(_name: Name<'_>) -> Option<Self> {
 		Some(Self)
 	}
 
-	fn parse_element_attribute(&mut self, _key: &str, _value: Cow<'_, str>) -> Result<()> {
+	fn parse_element_attribute(
+		&mut self,
+		_name: Name<'_>,
+		_value: Cow<'a, str>,
+		_context: &mut dyn ParseContext,
+	) -> Result<()> {
+		Ok(())
+	}
+
+	fn parse_element_inner_text(
+		&mut self,
+		_text: Cow<'a, str>,
+		_context: &mut dyn ParseContext,
+	) -> Result<()> {
 		Ok(())
 	}
 
-	fn parse_element_inner_text(&mut self, _text: Cow<'_, str>) -> Result<()> {
+	fn parse_element_inner_pi(
+		&mut self,
+		_pi: ProcessingInstruction<'a>,
+		_context: &mut dyn ParseContext,
+	) -> Result<()> {
 		Ok(())
 	}
 
-	fn parse_element_inner_node<P: ElementParser>(&mut self, _tag: &str, parser: P) -> Result<()> {
+	fn parse_element_inner_node<P: ElementParser<'a>>(
+		&mut self,
+		_name: Name<'_>,
+		parser: P,
+	) -> Result<()> {
 		// no need to create a new `IgnoreElement` state, just reuse `self`
 		parser.parse_element_state(self)
 	}
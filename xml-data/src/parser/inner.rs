@@ -1,10 +1,14 @@
 use crate::{
+	errors,
 	parser::{
 		Element,
 		ElementParser,
 		ElementState,
 		Inner,
+		ParseContext,
+		ProcessingInstruction,
 	},
+	Name,
 	Result,
 };
 use std::borrow::Cow;
@@ -20,44 +24,80 @@ pub enum InnerParseResult<Input> {
 }
 
 /// State to parse multiple elements (on the same level)
-pub trait InnerState: Default {
+///
+/// `'a` is the lifetime of the input data; see [`ElementState`].
+pub trait InnerState<'a>: Default {
 	/// Once fully parsed this is the resulting output type.
 	type Output: Sized;
 
-	/// Try parsing an element with the given tag
+	/// Try parsing an element with the given name
 	///
-	/// Should not fail if it doesn't recognize the tag; instead it needs to return the parser.
-	fn parse_inner_node<P: ElementParser>(
+	/// Should not fail if it doesn't recognize the name; instead it needs to return the parser.
+	fn parse_inner_node<P: ElementParser<'a>>(
 		&mut self,
-		tag: &str,
+		name: Name<'_>,
 		parser: P,
 	) -> Result<InnerParseResult<P>> {
-		let _ = tag;
+		let _ = name;
 		Ok(InnerParseResult::Next(parser))
 	}
 
 	/// Try parsing inner text
 	///
 	/// Should not fail if it doesn't take text (but may fail if it does but can't parse it).
-	fn parse_inner_text<'t>(
+	fn parse_inner_text(
 		&mut self,
-		text: Cow<'t, str>,
-	) -> Result<InnerParseResult<Cow<'t, str>>> {
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<Cow<'a, str>>> {
+		let _ = context;
 		Ok(InnerParseResult::Next(text))
 	}
 
+	/// Try parsing a comment
+	///
+	/// Should not fail if it doesn't take comments.
+	fn parse_inner_comment(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<Cow<'a, str>>> {
+		let _ = context;
+		Ok(InnerParseResult::Next(text))
+	}
+
+	/// Try parsing a processing instruction
+	///
+	/// Should not fail if it doesn't take (this) processing instruction.
+	fn parse_inner_pi(
+		&mut self,
+		pi: ProcessingInstruction<'a>,
+		context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<ProcessingInstruction<'a>>> {
+		let _ = context;
+		Ok(InnerParseResult::Next(pi))
+	}
+
+	/// Add tags [`InnerState::parse_inner_node`] would accept now (for error messages)
+	///
+	/// The default implementation doesn't add any.
+	fn expected_tags(&self, tags: &mut Vec<Name<'static>>) {
+		let _ = tags;
+	}
+
 	/// Finish parsing.
 	fn parse_inner_finish(self) -> Result<Self::Output>;
 }
 
 /// Using `String` as `InnerState` to collect all inner text in it (including whitespace in input)
-impl InnerState for String {
+impl<'a> InnerState<'a> for String {
 	type Output = Self;
 
-	fn parse_inner_text<'t>(
+	fn parse_inner_text(
 		&mut self,
-		text: Cow<'t, str>,
-	) -> Result<InnerParseResult<Cow<'t, str>>> {
+		text: Cow<'a, str>,
+		_context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<Cow<'a, str>>> {
 		if self.is_empty() {
 			*self = text.into_owned();
 		} else {
@@ -71,29 +111,24 @@ impl InnerState for String {
 	}
 }
 
-impl Inner for String {
+impl<'a> Inner<'a> for String {
 	type ParseState = String;
 }
 
-impl InnerState for Cow<'_, str> {
+/// Using `Cow<str>` as `InnerState` to collect all inner text in it (including whitespace in
+/// input); borrows from the input if there is only a single (borrowed) text.
+impl<'a: 'b, 'b> InnerState<'a> for Cow<'b, str> {
 	type Output = Self;
 
-	fn parse_inner_text<'t>(
+	fn parse_inner_text(
 		&mut self,
-		text: Cow<'t, str>,
-	) -> Result<InnerParseResult<Cow<'t, str>>> {
+		text: Cow<'a, str>,
+		_context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<Cow<'a, str>>> {
 		if self.is_empty() {
-			*self = Cow::Owned(text.into_owned());
+			*self = text;
 		} else {
-			match self {
-				Cow::Borrowed(s) => {
-					let t = String::from(*s) + &text;
-					*self = Cow::Owned(t);
-				},
-				Cow::Owned(s) => {
-					*s += &text;
-				},
-			}
+			*self.to_mut() += &text;
 		}
 		Ok(InnerParseResult::Success)
 	}
@@ -103,27 +138,60 @@ impl InnerState for Cow<'_, str> {
 	}
 }
 
+impl<'a: 'b, 'b> Inner<'a> for Cow<'b, str> {
+	type ParseState = Cow<'b, str>;
+}
+
+/// Using `&str` as `InnerState` to borrow all inner text from the input; fails if the text isn't
+/// a single borrowed text.
+impl<'a: 'b, 'b> InnerState<'a> for &'b str {
+	type Output = Self;
+
+	fn parse_inner_text(
+		&mut self,
+		text: Cow<'a, str>,
+		_context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<Cow<'a, str>>> {
+		if text.is_empty() {
+			// nothing to do
+		} else if let (true, Cow::Borrowed(text)) = (self.is_empty(), text) {
+			*self = text;
+		} else {
+			return Err(errors::cannot_borrow());
+		}
+		Ok(InnerParseResult::Success)
+	}
+
+	fn parse_inner_finish(self) -> Result<Self::Output> {
+		Ok(self)
+	}
+}
+
+impl<'a: 'b, 'b> Inner<'a> for &'b str {
+	type ParseState = &'b str;
+}
+
 /// `InnerState` to parse a single element
-pub struct ParseElementOnce<E: ElementState> {
+pub struct ParseElementOnce<'a, E: ElementState<'a>> {
 	element: Option<E::Output>,
 }
 
-impl<E: ElementState> Default for ParseElementOnce<E> {
+impl<'a, E: ElementState<'a>> Default for ParseElementOnce<'a, E> {
 	fn default() -> Self {
 		Self { element: None }
 	}
 }
 
-impl<E: ElementState> InnerState for ParseElementOnce<E> {
+impl<'a, E: ElementState<'a>> InnerState<'a> for ParseElementOnce<'a, E> {
 	type Output = E::Output;
 
-	fn parse_inner_node<P: ElementParser>(
+	fn parse_inner_node<P: ElementParser<'a>>(
 		&mut self,
-		tag: &str,
+		name: Name<'_>,
 		parser: P,
 	) -> Result<InnerParseResult<P>> {
 		if self.element.is_none() {
-			if let Some(mut state) = E::parse_element_start(tag) {
+			if let Some(mut state) = E::parse_element_start(name) {
 				parser.parse_element_state(&mut state)?;
 				self.element = Some(state.parse_element_finish()?);
 				return Ok(InnerParseResult::Success);
@@ -132,6 +200,12 @@ impl<E: ElementState> InnerState for ParseElementOnce<E> {
 		Ok(InnerParseResult::Next(parser))
 	}
 
+	fn expected_tags(&self, tags: &mut Vec<Name<'static>>) {
+		if self.element.is_none() {
+			E::expected_tags(tags);
+		}
+	}
+
 	fn parse_inner_finish(self) -> Result<Self::Output> {
 		if let Some(o) = self.element {
 			Ok(o)
@@ -141,31 +215,31 @@ impl<E: ElementState> InnerState for ParseElementOnce<E> {
 	}
 }
 
-impl<E: Element> Inner for E {
-	type ParseState = ParseElementOnce<E::ParseState>;
+impl<'a, E: Element<'a>> Inner<'a> for E {
+	type ParseState = ParseElementOnce<'a, E::ParseState>;
 }
 
 /// `InnerState` to parse a single optional element
-pub struct ParseElementOptional<E: ElementState> {
+pub struct ParseElementOptional<'a, E: ElementState<'a>> {
 	element: Option<E::Output>,
 }
 
-impl<E: ElementState> Default for ParseElementOptional<E> {
+impl<'a, E: ElementState<'a>> Default for ParseElementOptional<'a, E> {
 	fn default() -> Self {
 		Self { element: None }
 	}
 }
 
-impl<E: ElementState> InnerState for ParseElementOptional<E> {
+impl<'a, E: ElementState<'a>> InnerState<'a> for ParseElementOptional<'a, E> {
 	type Output = Option<E::Output>;
 
-	fn parse_inner_node<P: ElementParser>(
+	fn parse_inner_node<P: ElementParser<'a>>(
 		&mut self,
-		tag: &str,
+		name: Name<'_>,
 		parser: P,
 	) -> Result<InnerParseResult<P>> {
 		if self.element.is_none() {
-			if let Some(mut state) = E::parse_element_start(tag) {
+			if let Some(mut state) = E::parse_element_start(name) {
 				parser.parse_element_state(&mut state)?;
 				self.element = Some(state.parse_element_finish()?);
 				return Ok(InnerParseResult::Success);
@@ -174,21 +248,27 @@ impl<E: ElementState> InnerState for ParseElementOptional<E> {
 		Ok(InnerParseResult::Next(parser))
 	}
 
+	fn expected_tags(&self, tags: &mut Vec<Name<'static>>) {
+		if self.element.is_none() {
+			E::expected_tags(tags);
+		}
+	}
+
 	fn parse_inner_finish(self) -> Result<Self::Output> {
 		Ok(self.element)
 	}
 }
 
-impl<E: Element> Inner for Option<E> {
-	type ParseState = ParseElementOptional<E::ParseState>;
+impl<'a, E: Element<'a>> Inner<'a> for Option<E> {
+	type ParseState = ParseElementOptional<'a, E::ParseState>;
 }
 
 /// `InnerState` to parse multiple occurences of a single element
-pub struct ParseElementList<E: ElementState> {
+pub struct ParseElementList<'a, E: ElementState<'a>> {
 	elements: Vec<E::Output>,
 }
 
-impl<E: ElementState> Default for ParseElementList<E> {
+impl<'a, E: ElementState<'a>> Default for ParseElementList<'a, E> {
 	fn default() -> Self {
 		Self {
 			elements: Vec::new(),
@@ -196,15 +276,15 @@ impl<E: ElementState> Default for ParseElementList<E> {
 	}
 }
 
-impl<E: ElementState> InnerState for ParseElementList<E> {
+impl<'a, E: ElementState<'a>> InnerState<'a> for ParseElementList<'a, E> {
 	type Output = Vec<E::Output>;
 
-	fn parse_inner_node<P: ElementParser>(
+	fn parse_inner_node<P: ElementParser<'a>>(
 		&mut self,
-		tag: &str,
+		name: Name<'_>,
 		parser: P,
 	) -> Result<InnerParseResult<P>> {
-		if let Some(mut state) = E::parse_element_start(tag) {
+		if let Some(mut state) = E::parse_element_start(name) {
 			parser.parse_element_state(&mut state)?;
 			self.elements.push(state.parse_element_finish()?);
 			Ok(InnerParseResult::Success)
@@ -213,37 +293,98 @@ impl<E: ElementState> InnerState for ParseElementList<E> {
 		}
 	}
 
+	fn expected_tags(&self, tags: &mut Vec<Name<'static>>) {
+		E::expected_tags(tags);
+	}
+
 	fn parse_inner_finish(self) -> Result<Self::Output> {
 		Ok(self.elements)
 	}
 }
 
-impl<E: Element> Inner for Vec<E> {
-	type ParseState = ParseElementList<E::ParseState>;
+impl<'a, E: Element<'a>> Inner<'a> for Vec<E> {
+	type ParseState = ParseElementList<'a, E::ParseState>;
+}
+
+/// `InnerState` to parse multiple occurences of a single element, keeping elements that failed to
+/// parse as errors
+///
+/// Uses [`ElementParser::parse_element_state_or_skip`] to skip the rest of an element after a
+/// recoverable error; fatal errors are still returned.
+pub struct ParseElementListLenient<'a, E: ElementState<'a>> {
+	elements: Vec<Result<E::Output>>,
+}
+
+impl<'a, E: ElementState<'a>> Default for ParseElementListLenient<'a, E> {
+	fn default() -> Self {
+		Self {
+			elements: Vec::new(),
+		}
+	}
+}
+
+impl<'a, E: ElementState<'a>> InnerState<'a> for ParseElementListLenient<'a, E> {
+	type Output = Vec<Result<E::Output>>;
+
+	fn parse_inner_node<P: ElementParser<'a>>(
+		&mut self,
+		name: Name<'_>,
+		parser: P,
+	) -> Result<InnerParseResult<P>> {
+		if let Some(mut state) = E::parse_element_start(name) {
+			let position = parser.position();
+			let result = parser
+				.parse_element_state_or_skip(&mut state)
+				.and_then(|()| state.parse_element_finish());
+			match result {
+				Err(e) if !e.is_recoverable() => return Err(e),
+				Err(e) => self.elements.push(Err(match position {
+					Some(position) => e.at(position),
+					None => e,
+				})),
+				Ok(element) => self.elements.push(Ok(element)),
+			}
+			Ok(InnerParseResult::Success)
+		} else {
+			Ok(InnerParseResult::Next(parser))
+		}
+	}
+
+	fn expected_tags(&self, tags: &mut Vec<Name<'static>>) {
+		E::expected_tags(tags);
+	}
+
+	fn parse_inner_finish(self) -> Result<Self::Output> {
+		Ok(self.elements)
+	}
+}
+
+impl<'a, E: Element<'a>> Inner<'a> for Vec<Result<E>> {
+	type ParseState = ParseElementListLenient<'a, E::ParseState>;
 }
 
 /// `InnerState` to parse optional inner data; if it parsed anything it needs to finish
-pub struct ParseInnerOptional<I: InnerState> {
+pub struct ParseInnerOptional<I> {
 	inner: Option<I>,
 }
 
-impl<I: InnerState> Default for ParseInnerOptional<I> {
+impl<I> Default for ParseInnerOptional<I> {
 	fn default() -> Self {
 		Self { inner: None }
 	}
 }
 
-impl<I: InnerState> InnerState for ParseInnerOptional<I> {
+impl<'a, I: InnerState<'a>> InnerState<'a> for ParseInnerOptional<I> {
 	type Output = Option<I::Output>;
 
-	fn parse_inner_node<P: ElementParser>(
+	fn parse_inner_node<P: ElementParser<'a>>(
 		&mut self,
-		tag: &str,
+		name: Name<'_>,
 		parser: P,
 	) -> Result<InnerParseResult<P>> {
 		if self.inner.is_none() {
 			let mut state = I::default();
-			match state.parse_inner_node(tag, parser)? {
+			match state.parse_inner_node(name, parser)? {
 				InnerParseResult::Success => (),
 				InnerParseResult::Next(parser) => return Ok(InnerParseResult::Next(parser)),
 			}
@@ -251,19 +392,41 @@ impl<I: InnerState> InnerState for ParseInnerOptional<I> {
 			self.inner = Some(state);
 			Ok(InnerParseResult::Success)
 		} else if let Some(inner) = &mut self.inner {
-			inner.parse_inner_node(tag, parser)
+			inner.parse_inner_node(name, parser)
+		} else {
+			unreachable!()
+		}
+	}
+
+	fn parse_inner_text(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<Cow<'a, str>>> {
+		if self.inner.is_none() {
+			let mut state = I::default();
+			match state.parse_inner_text(text, context)? {
+				InnerParseResult::Success => (),
+				InnerParseResult::Next(text) => return Ok(InnerParseResult::Next(text)),
+			}
+			// matched something successfully, remember state
+			self.inner = Some(state);
+			Ok(InnerParseResult::Success)
+		} else if let Some(inner) = &mut self.inner {
+			inner.parse_inner_text(text, context)
 		} else {
 			unreachable!()
 		}
 	}
 
-	fn parse_inner_text<'t>(
+	fn parse_inner_comment(
 		&mut self,
-		text: Cow<'t, str>,
-	) -> Result<InnerParseResult<Cow<'t, str>>> {
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<Cow<'a, str>>> {
 		if self.inner.is_none() {
 			let mut state = I::default();
-			match state.parse_inner_text(text)? {
+			match state.parse_inner_comment(text, context)? {
 				InnerParseResult::Success => (),
 				InnerParseResult::Next(text) => return Ok(InnerParseResult::Next(text)),
 			}
@@ -271,12 +434,40 @@ impl<I: InnerState> InnerState for ParseInnerOptional<I> {
 			self.inner = Some(state);
 			Ok(InnerParseResult::Success)
 		} else if let Some(inner) = &mut self.inner {
-			inner.parse_inner_text(text)
+			inner.parse_inner_comment(text, context)
 		} else {
 			unreachable!()
 		}
 	}
 
+	fn parse_inner_pi(
+		&mut self,
+		pi: ProcessingInstruction<'a>,
+		context: &mut dyn ParseContext,
+	) -> Result<InnerParseResult<ProcessingInstruction<'a>>> {
+		if self.inner.is_none() {
+			let mut state = I::default();
+			match state.parse_inner_pi(pi, context)? {
+				InnerParseResult::Success => (),
+				InnerParseResult::Next(pi) => return Ok(InnerParseResult::Next(pi)),
+			}
+			// matched something successfully, remember state
+			self.inner = Some(state);
+			Ok(InnerParseResult::Success)
+		} else if let Some(inner) = &mut self.inner {
+			inner.parse_inner_pi(pi, context)
+		} else {
+			unreachable!()
+		}
+	}
+
+	fn expected_tags(&self, tags: &mut Vec<Name<'static>>) {
+		match &self.inner {
+			Some(inner) => inner.expected_tags(tags),
+			None => I::default().expected_tags(tags),
+		}
+	}
+
 	fn parse_inner_finish(self) -> Result<Self::Output> {
 		Ok(if let Some(inner) = self.inner {
 			Some(inner.parse_inner_finish()?)
@@ -9,17 +9,30 @@
 //!
 //! If your data type should represent multiple elements you need to a state type implementing
 //! `InnerState`; the default state is defined by implementing `Inner`.  If `E` implements
-//! `Element`, `E`, `Option<E>`, and `Vec<E>` automatically implement `Inner`.
+//! `Element`, `E`, `Option<E>`, `Vec<E>`, and `Vec<Result<E>>` (keeping elements that failed to
+//! parse as errors) automatically implement `Inner`.
+//!
+//! All traits have a lifetime parameter `'a` for the input data: parsers reading from memory can
+//! pass borrowed text (as `Cow::Borrowed`), which allows parsing into `Cow<'a, str>` or `&'a str`
+//! without allocations.  Owned types (like `String`) implement the traits for all lifetimes.
 //!
 //! To implement parser adaptors for an XML library you need to implement `ElementParser`.
+//! Adaptors that track positions in the input attach them to errors and pass element spans to
+//! states (see [`Spanned`][`crate::Spanned`]).
+//!
+//! States ignoring data should record a [`Warning`] with [`ParseContext::warn`]; parsers make
+//! them available after parsing.
 
 mod core;
 mod default;
+mod empty;
 mod extensions;
 mod fixed_element;
 mod ignore;
 mod inner;
+mod spanned;
 mod value;
+pub(crate) mod warnings;
 
 #[cfg_attr(doc_cfg, doc(cfg(feature = "derive")))]
 #[cfg(feature = "derive")]
@@ -32,6 +45,8 @@ pub use self::{
 	core::{
 		ElementParser,
 		ElementState,
+		ParseContext,
+		ProcessingInstruction,
 	},
 	default::{
 		Element,
@@ -39,6 +54,7 @@ pub use self::{
 		Inner,
 		InnerDefaultParseState,
 	},
+	empty::EmptyElement,
 	extensions::ElementParserExt,
 	fixed_element::FixedElementState,
 	ignore::IgnoreElement,
@@ -46,13 +62,20 @@ pub use self::{
 		InnerParseResult,
 		InnerState,
 		ParseElementList,
+		ParseElementListLenient,
 		ParseElementOnce,
 		ParseElementOptional,
 		ParseInnerOptional,
 	},
+	spanned::SpannedState,
 	value::{
 		Value,
 		ValueDefault,
+		ValueQName,
 		ValueString,
 	},
+	warnings::{
+		Warning,
+		WarningKind,
+	},
 };
@@ -0,0 +1,98 @@
+use crate::{
+	parser::{
+		Element,
+		ElementParser,
+		ElementState,
+		ParseContext,
+		ProcessingInstruction,
+	},
+	Name,
+	Result,
+	Span,
+	Spanned,
+};
+use std::borrow::Cow;
+
+/// State to parse an element with `S` and record its span
+///
+/// This is the default state for [`Spanned`].
+pub struct SpannedState<S> {
+	state: S,
+	span: Span,
+}
+
+impl<'a, S: ElementState<'a>> ElementState<'a> for SpannedState<S> {
+	type Output = Spanned<S::Output>;
+
+	fn parse_element_start(name: Name<'_>) -> Option<Self> {
+		Some(Self {
+			state: S::parse_element_start(name)?,
+			span: Span::default(),
+		})
+	}
+
+	fn expected_tags(tags: &mut Vec<Name<'static>>) {
+		S::expected_tags(tags);
+	}
+
+	fn parse_element_attribute(
+		&mut self,
+		name: Name<'_>,
+		value: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		self.state.parse_element_attribute(name, value, context)
+	}
+
+	fn parse_element_inner_text(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		self.state.parse_element_inner_text(text, context)
+	}
+
+	fn parse_element_inner_comment(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		self.state.parse_element_inner_comment(text, context)
+	}
+
+	fn parse_element_inner_pi(
+		&mut self,
+		pi: ProcessingInstruction<'a>,
+		context: &mut dyn ParseContext,
+	) -> Result<()> {
+		self.state.parse_element_inner_pi(pi, context)
+	}
+
+	fn parse_element_inner_node<P: ElementParser<'a>>(
+		&mut self,
+		name: Name<'_>,
+		parser: P,
+	) -> Result<()> {
+		self.state.parse_element_inner_node(name, parser)
+	}
+
+	fn parse_element_finish(self) -> Result<Self::Output> {
+		Ok(Spanned {
+			span: self.span,
+			value: self.state.parse_element_finish()?,
+		})
+	}
+
+	fn parse_element_span(&mut self, span: Span) {
+		self.span = span;
+		self.state.parse_element_span(span);
+	}
+
+	fn parse_error_not_found<T>() -> Result<T> {
+		S::parse_error_not_found()
+	}
+}
+
+impl<'a, E: Element<'a>> Element<'a> for Spanned<E> {
+	type ParseState = SpannedState<E::ParseState>;
+}
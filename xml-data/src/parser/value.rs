@@ -1,39 +1,69 @@
-use crate::Result;
+use crate::{
+	errors,
+	namespace::QName,
+	parser::ParseContext,
+	Result,
+};
 use std::borrow::Cow;
 
 /// Trait to parse attributes and inner text
 ///
 /// This is implemented my "marker" types to decide how to parse a type (the same type can be
 /// parsed differently depending on the marker type)
-pub trait Value<T> {
-	/// Serialize value to text
-	fn parse_value(text: Cow<'_, str>) -> Result<T>;
+///
+/// `'a` is the lifetime of the input data the value can borrow from; `context` provides the
+/// namespace declarations in scope (see [`ParseContext::resolve_prefix`]).
+pub trait Value<'a, T> {
+	/// Parse value from text
+	fn parse_value(text: Cow<'a, str>, context: &dyn ParseContext) -> Result<T>;
 }
 
 /// Implements `Value` for all types implementing `std::str::FromStr`; this is a good default.
 pub struct ValueDefault;
 
-impl<T> Value<T> for ValueDefault
+impl<'a, T> Value<'a, T> for ValueDefault
 where
 	T: std::str::FromStr,
-	T::Err: std::error::Error + 'static,
+	T::Err: std::error::Error + Send + Sync + 'static,
 {
-	fn parse_value(text: Cow<'_, str>) -> Result<T> {
-		Ok(text.parse::<T>()?)
+	fn parse_value(text: Cow<'a, str>, _context: &dyn ParseContext) -> Result<T> {
+		text.parse::<T>().map_err(errors::invalid_value)
 	}
 }
 
-/// Implements `Value` for `String` and `Cow<str>`.
+/// Implements `Value` for `String`, `Cow<str>` and `&str`.
+///
+/// `Cow<str>` borrows from the input if possible; `&str` fails if the parser can't borrow the text
+/// from the input (e.g. because it needed unescaping).
 pub struct ValueString;
 
-impl Value<String> for ValueString {
-	fn parse_value(text: Cow<'_, str>) -> Result<String> {
+impl<'a> Value<'a, String> for ValueString {
+	fn parse_value(text: Cow<'a, str>, _context: &dyn ParseContext) -> Result<String> {
 		Ok(text.into_owned())
 	}
 }
 
-impl<'a> Value<Cow<'a, str>> for ValueString {
-	fn parse_value(text: Cow<'_, str>) -> Result<Cow<'a, str>> {
-		Ok(Cow::Owned(text.into_owned()))
+impl<'a: 'b, 'b> Value<'a, Cow<'b, str>> for ValueString {
+	fn parse_value(text: Cow<'a, str>, _context: &dyn ParseContext) -> Result<Cow<'b, str>> {
+		Ok(text)
+	}
+}
+
+impl<'a: 'b, 'b> Value<'a, &'b str> for ValueString {
+	fn parse_value(text: Cow<'a, str>, _context: &dyn ParseContext) -> Result<&'b str> {
+		match text {
+			Cow::Borrowed(text) => Ok(text),
+			Cow::Owned(_) => Err(errors::cannot_borrow()),
+		}
+	}
+}
+
+/// Implements `Value` for [`QName`]: resolves the prefix with the namespace declarations in scope
+/// (see [`QName::resolve`])
+pub struct ValueQName;
+
+impl<'a> Value<'a, QName> for ValueQName {
+	fn parse_value(text: Cow<'a, str>, context: &dyn ParseContext) -> Result<QName> {
+		QName::resolve(&text, context)
 	}
 }
@@ -0,0 +1,127 @@
+use crate::{
+	errors::{
+		ErrorPath,
+		PathSegment,
+	},
+	Position,
+};
+use std::fmt;
+
+/// Kinds of warnings
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningKind {
+	/// Attribute was ignored
+	IgnoredAttribute {
+		/// Key of the attribute
+		key: String,
+	},
+	/// Element was ignored (with all its content)
+	IgnoredElement {
+		/// Tag of the element
+		tag: String,
+	},
+	/// Non-whitespace text was ignored
+	IgnoredText {
+		/// The ignored text
+		text: String,
+	},
+}
+
+impl fmt::Display for WarningKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::IgnoredAttribute { key } => write!(f, "Ignored attribute: {}", key),
+			Self::IgnoredElement { tag } => write!(f, "Ignored element: {}", tag),
+			Self::IgnoredText { text } => write!(f, "Ignored text: {:?}", text),
+		}
+	}
+}
+
+/// Non-fatal problem found while parsing
+///
+/// See [`ParseContext::warn`][super::ParseContext::warn].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+	kind: WarningKind,
+	path: ErrorPath,
+	position: Option<Position>,
+}
+
+impl Warning {
+	/// Warning for the element at `path` (root first); for [`WarningKind::IgnoredAttribute`] the
+	/// attribute is added to the path
+	pub(crate) fn new(kind: WarningKind, path: &[PathSegment], position: Option<Position>) -> Self {
+		let mut path = path.to_vec();
+		if let WarningKind::IgnoredAttribute { key } = &kind {
+			path.push(PathSegment::Attribute { key: key.clone() });
+		}
+		Self {
+			kind,
+			path: ErrorPath::from_segments(path),
+			position,
+		}
+	}
+
+	/// Kind of warning
+	pub fn kind(&self) -> &WarningKind {
+		&self.kind
+	}
+
+	/// Location of the warning in the document (might be empty if unknown)
+	pub fn path(&self) -> &ErrorPath {
+		&self.path
+	}
+
+	/// Position in the input (if the parser tracks positions)
+	pub fn position(&self) -> Option<Position> {
+		self.position
+	}
+}
+
+impl fmt::Display for Warning {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(position) = &self.position {
+			write!(f, "{}: ", position)?;
+		}
+		if !self.path.is_empty() {
+			write!(f, "{}: ", self.path)?;
+		}
+		fmt::Display::fmt(&self.kind, f)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_display() {
+		let path = [
+			PathSegment::Element {
+				tag: "data".into(),
+				index: None,
+			},
+			PathSegment::Element {
+				tag: "foo".into(),
+				index: Some(2),
+			},
+		];
+		let warnings = [
+			Warning::new(
+				WarningKind::IgnoredAttribute { key: "x".into() },
+				&path[..1],
+				Some(Position::START),
+			),
+			Warning::new(WarningKind::IgnoredText { text: "z".into() }, &path, None),
+		];
+		let warnings: Vec<_> = warnings.iter().map(ToString::to_string).collect();
+		assert_eq!(
+			warnings,
+			[
+				"line 1, column 1: /data/@x: Ignored attribute: x",
+				"/data/foo[2]: Ignored text: \"z\"",
+			]
+		);
+	}
+}
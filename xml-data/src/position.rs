@@ -0,0 +1,93 @@
+use std::{
+	fmt,
+	ops,
+};
+
+/// Position in the input of a parser
+///
+/// Parsers that don't track positions leave this at the default (all zero).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+	/// Byte offset (starting at 0)
+	pub offset: usize,
+	/// Line number (starting at 1)
+	pub line: usize,
+	/// Column in characters (starting at 1)
+	pub column: usize,
+}
+
+impl Position {
+	/// Position of the first character in the input
+	pub const START: Self = Self {
+		offset: 0,
+		line: 1,
+		column: 1,
+	};
+
+	/// Advance over input bytes
+	pub(crate) fn advance(&mut self, bytes: &[u8]) {
+		for &b in bytes {
+			self.offset += 1;
+			if b == b'\n' {
+				self.line += 1;
+				self.column = 1;
+			} else if b & 0xc0 != 0x80 {
+				// not a utf-8 continuation byte
+				self.column += 1;
+			}
+		}
+	}
+
+	/// Advance to offset, assuming the skipped bytes don't contain line breaks
+	pub(crate) fn skip_to(&mut self, offset: usize) {
+		if offset > self.offset {
+			self.column += offset - self.offset;
+			self.offset = offset;
+		}
+	}
+}
+
+impl fmt::Display for Position {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "line {}, column {}", self.line, self.column)
+	}
+}
+
+/// Range in the input of a parser
+///
+/// `end` is the position after the last byte of the range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+	/// Start of range
+	pub start: Position,
+	/// End of range (exclusive)
+	pub end: Position,
+}
+
+/// Value with the span it was parsed from
+///
+/// `Spanned<E>` is parsed like `E` (see [`SpannedState`][`crate::parser::SpannedState`]) and
+/// records the span of the element; serializing ignores the span.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Spanned<T> {
+	/// Span of the element in the input
+	///
+	/// All zero if the parser doesn't track positions.
+	pub span: Span,
+	/// Parsed value
+	pub value: T,
+}
+
+impl<T> ops::Deref for Spanned<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.value
+	}
+}
+
+impl<T> ops::DerefMut for Spanned<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.value
+	}
+}
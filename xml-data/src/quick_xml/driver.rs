@@ -0,0 +1,1189 @@
+//! Parser implementation shared by the buffered and borrowing adaptors
+//!
+//! The adaptors decode `quick-xml` events into [`Node`]s, which either own their data or borrow it
+//! from the input (but never from a reusable read buffer); this way an element start can be
+//! passed on to nested parsers without keeping a reference into the read buffer.
+//!
+//! Namespaces are resolved here (and not with the resolver in `quick-xml`, which only works with
+//! buffered reading) so both adaptors behave the same; the declarations in scope are available to
+//! values through [`ParseContext::resolve_prefix`].
+
+use crate::{
+	document::{
+		Declaration,
+		Document,
+		Misc,
+	},
+	errors::{
+		self,
+		PathSegment,
+	},
+	extensions::*,
+	namespace::{
+		split_qname,
+		Namespaces,
+	},
+	parser::{
+		ElementParser,
+		ElementState,
+		IgnoreElement,
+		ParseContext,
+		ProcessingInstruction,
+		Warning,
+		WarningKind,
+	},
+	Error,
+	Name,
+	Position,
+	Result,
+	Span,
+};
+use quick_xml::events::BytesDecl;
+use std::{
+	borrow::Cow,
+	collections::HashMap,
+	sync::Arc,
+};
+
+/// Decoded start tag (or empty element tag)
+pub(super) struct Start<'i> {
+	/// Qualified name (with prefix)
+	pub tag: Cow<'i, str>,
+	/// Resolved namespace (set by the driver)
+	pub namespace: Option<Arc<str>>,
+	pub attributes: Vec<Attribute<'i>>,
+	pub empty: bool,
+}
+
+/// Decoded attribute
+pub(super) struct Attribute<'i> {
+	/// Qualified name (with prefix)
+	pub key: Cow<'i, str>,
+	/// Resolved namespace (set by the driver)
+	pub namespace: Option<Arc<str>>,
+	pub value: Cow<'i, str>,
+}
+
+/// Expanded name from qualified name and resolved namespace
+fn name<'n>(qname: &'n str, namespace: &'n Option<Arc<str>>) -> Name<'n> {
+	Name::new(namespace.as_deref(), split_qname(qname).1)
+}
+
+/// Decoded event
+pub(super) enum Node<'i> {
+	Start(Start<'i>),
+	End,
+	/// Unescaped text or CDATA
+	Text(Cow<'i, str>),
+	/// Comment text
+	Comment(Cow<'i, str>),
+	/// Content of processing instruction (target and data)
+	PI(Cow<'i, str>),
+	Decl(Declaration),
+	/// Content of DOCTYPE declaration
+	DocType(Cow<'i, str>),
+	Eof,
+}
+
+/// Source of decoded events
+pub(super) trait Source<'i> {
+	/// Read next node and the position it starts at
+	///
+	/// Errors should already have a position attached.
+	fn read_node(&mut self) -> Result<(Position, Node<'i>)>;
+
+	/// Position after the last node read
+	fn position(&self) -> Position;
+}
+
+/// State shared by all levels while parsing a document
+struct Driver<'x, R> {
+	source: &'x mut R,
+	/// Recoverable errors recorded so far (if collecting errors)
+	collected: Option<Vec<Error>>,
+	warnings: &'x mut Vec<Warning>,
+	/// Path of the element currently parsed (root first; for warnings)
+	path: Vec<PathSegment>,
+	/// Position of the node currently parsed (for warnings)
+	position: Position,
+	/// Declarations in scope of the node currently parsed
+	namespaces: Namespaces,
+	/// Number of open elements
+	depth: usize,
+	/// Scope of the last empty element is still open (so its attributes can resolve prefixes in
+	/// values); closed when reading the next node
+	pending_pop: bool,
+}
+
+impl<'x, 'i, R: Source<'i>> Driver<'x, R> {
+	fn read_node(&mut self) -> Result<(Position, Node<'i>)> {
+		if std::mem::take(&mut self.pending_pop) {
+			self.namespaces.pop_scope();
+		}
+		let (position, mut node) = self.source.read_node()?;
+		match &mut node {
+			Node::Start(start) => {
+				self.resolve(start).map_err(|e| e.at(position))?;
+				if !start.empty {
+					self.depth += 1;
+				}
+			},
+			Node::End => {
+				self.namespaces.pop_scope();
+				self.depth = self.depth.saturating_sub(1);
+			},
+			_ => (),
+		}
+		Ok((position, node))
+	}
+
+	/// Resolve namespaces of element and attributes; removes namespace declarations
+	fn resolve(&mut self, start: &mut Start<'i>) -> Result<()> {
+		self.pending_pop = start.empty;
+		let namespaces = &mut self.namespaces;
+		namespaces.push_scope();
+		for attr in &start.attributes {
+			match split_qname(&attr.key) {
+				(None, "xmlns") => namespaces.declare("", &attr.value),
+				(Some("xmlns"), prefix) => namespaces.declare(prefix, &attr.value),
+				_ => (),
+			}
+		}
+		start
+			.attributes
+			.retain(|attr| attr.key != "xmlns" && split_qname(&attr.key).0 != Some("xmlns"));
+
+		start.namespace = match split_qname(&start.tag) {
+			(Some(prefix), _) => Some(
+				namespaces
+					.resolve(prefix)
+					.ok_or_else(|| errors::unbound_prefix(prefix))?,
+			),
+			(None, _) => namespaces.resolve(""),
+		};
+		for attr in &mut start.attributes {
+			// unprefixed attributes are not in the default namespace
+			if let (Some(prefix), _) = split_qname(&attr.key) {
+				attr.namespace = Some(
+					namespaces
+						.resolve(prefix)
+						.ok_or_else(|| errors::unbound_prefix(prefix))?,
+				);
+			}
+		}
+		Ok(())
+	}
+
+	/// Skip nodes until the open element at `level` is closed
+	fn skip_to_level(&mut self, level: usize) -> Result<()> {
+		while self.depth >= level {
+			if let (position, Node::Eof) = self.read_node()? {
+				return Err(errors::unexpected_eof("unclosed element").at(position));
+			}
+		}
+		Ok(())
+	}
+
+	/// Record recoverable error if collecting errors; otherwise fail with it
+	fn recover(&mut self, e: Error) -> Result<()> {
+		match &mut self.collected {
+			Some(errors) if e.is_recoverable() => {
+				errors.push(e);
+				Ok(())
+			},
+			_ => Err(e),
+		}
+	}
+
+	fn recorded(&self) -> usize {
+		self.collected.as_ref().map_or(0, Vec::len)
+	}
+
+	/// Enter element starting at `position` (for warnings)
+	fn enter_element(&mut self, tag: &str, index: Option<usize>, position: Position) {
+		self.path.push(PathSegment::Element {
+			tag: tag.into(),
+			index,
+		});
+		self.position = position;
+	}
+
+	/// Leave element entered with [`Driver::enter_element`]
+	fn leave_element(&mut self) {
+		self.path.pop();
+	}
+
+	/// Mark errors recorded since `mark` as happening within the given element
+	fn mark_element(&mut self, mark: usize, tag: &str, index: Option<usize>) {
+		if let Some(errors) = &mut self.collected {
+			for e in &mut errors[mark..] {
+				e.push_element(tag, index);
+			}
+		}
+	}
+}
+
+impl<R> ParseContext for Driver<'_, R> {
+	fn warn(&mut self, kind: WarningKind) {
+		let warning = Warning::new(kind, &self.path, Some(self.position));
+		self.warnings.push(warning);
+	}
+
+	fn resolve_prefix(&self, prefix: &str) -> Option<&str> {
+		self.namespaces.resolve_prefix(prefix)
+	}
+}
+
+/// Decode XML declaration
+pub(super) fn declaration<B: std::io::BufRead>(
+	reader: &quick_xml::Reader<B>,
+	decl: &BytesDecl<'_>,
+) -> Result<Declaration> {
+	let version = reader.decode(&decl.version()?).into_owned();
+	let encoding = match decl.encoding() {
+		Some(encoding) => Some(reader.decode(&encoding?).into_owned()),
+		None => None,
+	};
+	let standalone = match decl.standalone() {
+		Some(standalone) => match &*standalone? {
+			b"yes" => Some(true),
+			b"no" => Some(false),
+			value => {
+				return Err(errors::syntax(format!(
+					"invalid standalone value: {:?}",
+					reader.decode(value)
+				)))
+			},
+		},
+		None => None,
+	};
+	Ok(Declaration {
+		version,
+		encoding,
+		standalone,
+	})
+}
+
+/// Parse a single (root) element from reading a document
+///
+/// If `collect_errors` is set recoverable errors are recorded and parsing continues; if any
+/// error was recorded the result is an [`ErrorKind::Multiple`][`crate::ErrorKind::Multiple`]
+/// error containing all of them.
+///
+/// The declaration, DOCTYPE, comments and processing instructions outside the root element are
+/// recorded in `document` if given; otherwise comments are ignored and processing instructions
+/// rejected.  Warnings are added to `warnings`.
+pub(super) fn parse_document<'i, R, S>(
+	source: &mut R,
+	collect_errors: bool,
+	document: Option<&mut Document<()>>,
+	warnings: &mut Vec<Warning>,
+) -> Result<S::Output>
+where
+	R: Source<'i>,
+	S: ElementState<'i>,
+{
+	let mut driver = Driver {
+		source,
+		collected: if collect_errors {
+			Some(Vec::new())
+		} else {
+			None
+		},
+		warnings,
+		path: Vec::new(),
+		position: Position::START,
+		namespaces: Namespaces::default(),
+		depth: 0,
+		pending_pop: false,
+	};
+	let result = parse_root::<R, S>(&mut driver, document);
+	match (driver.collected, result) {
+		(Some(errors), Ok(output)) if errors.is_empty() => Ok(output),
+		(Some(errors), Ok(_)) => Err(errors::multiple(errors)),
+		(Some(mut errors), Err(e)) => {
+			errors.push(e);
+			Err(errors::multiple(errors))
+		},
+		(None, result) => result,
+	}
+}
+
+/// List for comments and processing instructions at the current place in the document
+fn misc_list(document: &mut Document<()>, after_root: bool) -> &mut Vec<Misc> {
+	if after_root {
+		&mut document.epilog
+	} else if document.doctype.is_some() {
+		&mut document.after_doctype
+	} else {
+		&mut document.prolog
+	}
+}
+
+fn parse_root<'i, R, S>(
+	driver: &mut Driver<'_, R>,
+	mut document: Option<&mut Document<()>>,
+) -> Result<S::Output>
+where
+	R: Source<'i>,
+	S: ElementState<'i>,
+{
+	let mut output = None;
+	loop {
+		let (position, node) = driver.read_node()?;
+		match node {
+			Node::Eof => {
+				if let Some(o) = output {
+					return Ok(o);
+				}
+				return Err(errors::unexpected_eof("empty document").at(position));
+			},
+			Node::End => {
+				return Err(errors::unexpected_end().at(position));
+			},
+			Node::Start(Start {
+				tag,
+				namespace,
+				attributes,
+				empty,
+			}) => {
+				let mark = driver.recorded();
+				driver.enter_element(&tag, None, position);
+				let mut finished_inner = false;
+				let p = PRef {
+					driver: &mut *driver,
+					start: position,
+					attributes,
+					empty,
+					finished_element: &mut finished_inner,
+				};
+				let result = p.parse_element::<S>(name(&tag, &namespace));
+				driver.leave_element();
+				driver.mark_element(mark, &tag, None);
+				output = Some(result.map_err(|e| e.in_element(&tag, None).at(position))?);
+				if !finished_inner {
+					return Err(errors::inner_element_not_parsed(&tag)
+						.in_element(&tag, None)
+						.at(position));
+				}
+			},
+			Node::PI(content) => match &mut document {
+				Some(document) => {
+					let pi = ProcessingInstruction::from_content(content);
+					misc_list(document, output.is_some()).push(Misc::ProcessingInstruction {
+						target: pi.target.into_owned(),
+						data: pi.data.into_owned(),
+					});
+				},
+				// no state outside the root element
+				None => return Err(errors::unexpected_pi().at(position)),
+			},
+			// only allowed before the root element
+			Node::Decl(declaration) => {
+				if output.is_some() {
+					return Err(errors::unexpected_decl().at(position));
+				}
+				if let Some(document) = &mut document {
+					if document.declaration.replace(declaration).is_some() {
+						return Err(errors::unexpected_decl().at(position));
+					}
+				}
+			},
+			Node::DocType(content) => {
+				if output.is_some() {
+					return Err(errors::unexpected_doctype().at(position));
+				}
+				if let Some(document) = &mut document {
+					if document.doctype.replace(content.into_owned()).is_some() {
+						return Err(errors::unexpected_doctype().at(position));
+					}
+				}
+			},
+			Node::Comment(text) => {
+				if let Some(document) = &mut document {
+					misc_list(document, output.is_some()).push(Misc::Comment(text.into_owned()));
+				}
+			},
+			// text+cdata
+			Node::Text(t) => {
+				if !t.trim().is_empty() {
+					return Err(errors::unexpected_text().at(position));
+				}
+			},
+		}
+	}
+}
+
+/// What to do with children of the root element the item state doesn't accept when streaming
+/// (see [`Parser::stream_children`][super::Parser::stream_children])
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UnmatchedChildren {
+	/// Skip them silently
+	#[default]
+	Skip,
+	/// Skip them and record a warning
+	Warn,
+	/// Skip them and yield an "unexpected element" error (iteration continues afterwards)
+	Error,
+}
+
+/// Driver state kept between parsing single children of the root element
+#[derive(Default)]
+struct DriverState {
+	namespaces: Namespaces,
+	depth: usize,
+	pending_pop: bool,
+}
+
+impl DriverState {
+	fn run<'i, R: Source<'i>, T>(
+		&mut self,
+		source: &mut R,
+		warnings: &mut Vec<Warning>,
+		f: impl FnOnce(&mut Driver<'_, R>) -> T,
+	) -> T {
+		let mut driver = Driver {
+			source,
+			collected: None,
+			warnings,
+			path: Vec::new(),
+			position: Position::START,
+			namespaces: std::mem::take(&mut self.namespaces),
+			depth: self.depth,
+			pending_pop: self.pending_pop,
+		};
+		let result = f(&mut driver);
+		self.namespaces = driver.namespaces;
+		self.depth = driver.depth;
+		self.pending_pop = driver.pending_pop;
+		result
+	}
+}
+
+/// Document whose root element children are parsed one at a time
+pub(super) struct Stream {
+	driver: DriverState,
+	/// Tag of the root element (for error paths)
+	root: String,
+	root_closed: bool,
+	done: bool,
+	siblings: HashMap<String, usize>,
+	unmatched: UnmatchedChildren,
+	/// More input might follow the end of the source (push parsing)
+	partial: bool,
+}
+
+impl Stream {
+	/// Whether the end of the source only pauses parsing (until more input is available)
+	pub(super) fn set_partial(&mut self, partial: bool) {
+		self.partial = partial;
+	}
+
+	/// Whether the root element was closed (or parsing failed)
+	pub(super) fn is_done(&self) -> bool {
+		self.done
+	}
+}
+
+/// Result of reading the next child
+enum Step<T> {
+	/// Parsed (or failed to parse) a child
+	Item(Result<T>),
+	/// Reached end of document
+	End,
+	/// Failed to continue
+	Fatal(Error),
+	/// Reached end of partial input
+	Pause,
+}
+
+/// Read up to the start tag of the root element and parse its attributes into the state
+/// `header` creates
+///
+/// The state doesn't see any content; the children are parsed with [`next_child`].  Warnings are
+/// added to `warnings`.
+pub(super) fn start_stream<'i, R, H>(
+	source: &mut R,
+	header: impl FnOnce(Name<'_>) -> Option<H>,
+	unmatched: UnmatchedChildren,
+	warnings: &mut Vec<Warning>,
+) -> Result<(H::Output, Stream)>
+where
+	R: Source<'i>,
+	H: ElementState<'i>,
+{
+	let mut driver = DriverState::default();
+	let (output, root, empty) = driver.run(source, warnings, |driver| loop {
+		let (position, node) = driver.read_node()?;
+		match node {
+			Node::Start(Start {
+				tag,
+				namespace,
+				attributes,
+				empty,
+			}) => {
+				driver.enter_element(&tag, None, position);
+				let result = parse_header(header, name(&tag, &namespace), attributes, driver);
+				driver.leave_element();
+				let output = result.map_err(|e| e.in_element(&tag, None).at(position))?;
+				return Ok((output, tag.into_owned(), empty));
+			},
+			Node::Eof => return Err(errors::unexpected_eof("empty document").at(position)),
+			Node::End => return Err(errors::unexpected_end().at(position)),
+			Node::PI(_) => return Err(errors::unexpected_pi().at(position)),
+			Node::Text(t) if !t.trim().is_empty() => {
+				return Err(errors::unexpected_text().at(position));
+			},
+			Node::Decl(_) | Node::DocType(_) | Node::Comment(_) | Node::Text(_) => (),
+		}
+	})?;
+	let stream = Stream {
+		driver,
+		root,
+		root_closed: empty,
+		done: false,
+		siblings: HashMap::new(),
+		unmatched,
+		partial: false,
+	};
+	Ok((output, stream))
+}
+
+fn parse_header<'i, H: ElementState<'i>>(
+	header: impl FnOnce(Name<'_>) -> Option<H>,
+	name: Name<'_>,
+	attributes: Vec<Attribute<'i>>,
+	context: &mut dyn ParseContext,
+) -> Result<H::Output> {
+	let mut state = match header(name) {
+		Some(state) => state,
+		None => {
+			let mut expected = Vec::new();
+			H::expected_tags(&mut expected);
+			return Err(errors::unexpected_element_expected(
+				&name.to_string(),
+				&expected,
+			));
+		},
+	};
+	for attr in attributes {
+		let Attribute {
+			key,
+			namespace,
+			value,
+		} = attr;
+		state
+			.parse_element_attribute(self::name(&key, &namespace), value, context)
+			.map_err(|e| e.in_attribute(&key))?;
+	}
+	state.parse_element_finish()
+}
+
+/// Parse the next child of the root element the state `S` accepts
+///
+/// Returns `None` after the end of the document (or of partial input); after a fatal error the
+/// stream is done too.  Warnings are added to `warnings`.
+pub(super) fn next_child<'i, R, S>(
+	source: &mut R,
+	stream: &mut Stream,
+	warnings: &mut Vec<Warning>,
+) -> Option<Result<S::Output>>
+where
+	R: Source<'i>,
+	S: ElementState<'i>,
+{
+	if stream.done {
+		return None;
+	}
+	let Stream {
+		driver,
+		root,
+		root_closed,
+		siblings,
+		unmatched,
+		partial,
+		..
+	} = stream;
+	let step = driver.run(source, warnings, |driver| {
+		let position = driver.source.position();
+		driver.enter_element(root, None, position);
+		let step = read_child::<R, S>(driver, root_closed, siblings, *unmatched, *partial);
+		driver.leave_element();
+		step
+	});
+	match step {
+		Step::Item(Ok(output)) => Some(Ok(output)),
+		Step::Item(Err(e)) => {
+			stream.done = !e.is_recoverable();
+			Some(Err(e.in_element(&stream.root, None)))
+		},
+		Step::End => {
+			stream.done = true;
+			None
+		},
+		Step::Fatal(e) => {
+			stream.done = true;
+			Some(Err(e))
+		},
+		Step::Pause => None,
+	}
+}
+
+fn read_child<'i, R, S>(
+	driver: &mut Driver<'_, R>,
+	root_closed: &mut bool,
+	siblings: &mut HashMap<String, usize>,
+	unmatched: UnmatchedChildren,
+	partial: bool,
+) -> Step<S::Output>
+where
+	R: Source<'i>,
+	S: ElementState<'i>,
+{
+	loop {
+		let (position, node) = match driver.read_node() {
+			Ok(node) => node,
+			Err(e) => return Step::Fatal(e),
+		};
+		if *root_closed {
+			// only comments and whitespace after the root element
+			let e = match node {
+				Node::Eof => return Step::End,
+				Node::Comment(_) => continue,
+				Node::Text(t) if t.trim().is_empty() => continue,
+				Node::Text(_) => errors::unexpected_text(),
+				Node::Start(start) => errors::unexpected_element(&start.tag),
+				Node::End => errors::unexpected_end(),
+				Node::PI(_) => errors::unexpected_pi(),
+				Node::Decl(_) => errors::unexpected_decl(),
+				Node::DocType(_) => errors::unexpected_doctype(),
+			};
+			return Step::Fatal(e.at(position));
+		}
+		let (tag, namespace, attributes, empty) = match node {
+			Node::Start(Start {
+				tag,
+				namespace,
+				attributes,
+				empty,
+			}) => (tag, namespace, attributes, empty),
+			Node::End => {
+				*root_closed = true;
+				continue;
+			},
+			Node::Eof if partial => return Step::Pause,
+			Node::Eof => {
+				return Step::Fatal(errors::unexpected_eof("unclosed element").at(position));
+			},
+			Node::Comment(_) => continue,
+			Node::Text(t) if t.trim().is_empty() => continue,
+			Node::Text(_) => return Step::Item(Err(errors::unexpected_text().at(position))),
+			Node::PI(_) => return Step::Item(Err(errors::unexpected_pi().at(position))),
+			Node::Decl(_) => return Step::Fatal(errors::unexpected_decl().at(position)),
+			Node::DocType(_) => return Step::Fatal(errors::unexpected_doctype().at(position)),
+		};
+		let index = match siblings.get_mut(&*tag) {
+			Some(count) => {
+				*count += 1;
+				*count
+			},
+			None => {
+				siblings.insert(tag.to_string(), 1);
+				1
+			},
+		};
+		driver.enter_element(&tag, Some(index), position);
+		let mut finished_inner = false;
+		let p = PRef {
+			driver: &mut *driver,
+			start: position,
+			attributes,
+			empty,
+			finished_element: &mut finished_inner,
+		};
+		let name = name(&tag, &namespace);
+		let result = match S::parse_element_start(name) {
+			Some(mut state) => Some(
+				p.parse_element_state_or_skip(&mut state)
+					.and_then(|()| state.parse_element_finish()),
+			),
+			None => {
+				let result = match unmatched {
+					UnmatchedChildren::Skip | UnmatchedChildren::Error => {
+						p.parse_element_state(&mut IgnoreElement)
+					},
+					UnmatchedChildren::Warn => IgnoreElement::ignore(name, p),
+				};
+				match (result, unmatched) {
+					(Err(e), _) => Some(Err(e)),
+					(Ok(()), UnmatchedChildren::Error) => {
+						let mut expected = Vec::new();
+						S::expected_tags(&mut expected);
+						Some(Err(errors::unexpected_element_expected(
+							&name.to_string(),
+							&expected,
+						)))
+					},
+					(Ok(()), _) => None,
+				}
+			},
+		};
+		driver.leave_element();
+		match result {
+			Some(result) => {
+				return Step::Item(
+					result.map_err(|e| e.in_element(&tag, Some(index)).at(position)),
+				);
+			},
+			None => continue,
+		}
+	}
+}
+
+/// Parser for an element whose start tag was already read
+struct PRef<'x, 'd, 'i, R> {
+	driver: &'x mut Driver<'d, R>,
+	start: Position,
+	attributes: Vec<Attribute<'i>>,
+	empty: bool,
+	finished_element: &'x mut bool,
+}
+
+impl<'x, 'd, 'i, R: Source<'i>> PRef<'x, 'd, 'i, R> {
+	fn parse<E: ElementState<'i>>(&mut self, state: &mut E) -> Result<()> {
+		let start = self.start;
+		self.driver.position = start;
+		for attr in std::mem::take(&mut self.attributes) {
+			let Attribute {
+				key,
+				namespace,
+				value,
+			} = attr;
+			let name = name(&key, &namespace);
+			if let Err(e) = state.parse_element_attribute(name, value, &mut *self.driver) {
+				self.driver.recover(e.in_attribute(&key).at(start))?;
+			}
+		}
+
+		// read inner (unless there is no inner)
+		if !self.empty {
+			// count children per tag for error paths
+			let mut siblings = HashMap::<String, usize>::new();
+			loop {
+				let (position, node) = self.driver.read_node()?;
+				match node {
+					Node::Eof => {
+						return Err(errors::unexpected_eof("unclosed element").at(position))
+					},
+					Node::End => break,
+					Node::Start(Start {
+						tag,
+						namespace,
+						attributes,
+						empty,
+					}) => {
+						let index = match siblings.get_mut(&*tag) {
+							Some(count) => {
+								*count += 1;
+								*count
+							},
+							None => {
+								siblings.insert(tag.to_string(), 1);
+								1
+							},
+						};
+						let mark = self.driver.recorded();
+						self.driver.enter_element(&tag, Some(index), position);
+						let mut finished_inner = false;
+						let p = PRef {
+							driver: &mut *self.driver,
+							start: position,
+							attributes,
+							empty,
+							finished_element: &mut finished_inner,
+						};
+						let mut result = state.parse_element_inner_node(name(&tag, &namespace), p);
+						if result.is_ok() && !finished_inner {
+							result = Err(errors::inner_element_not_parsed(&tag));
+						}
+						if let Err(e) = result {
+							result = self.driver.recover(e.at(position));
+							if result.is_ok() && !finished_inner {
+								// skip the rest of the element
+								let p = PRef {
+									driver: &mut *self.driver,
+									start: position,
+									attributes: Vec::new(),
+									empty,
+									finished_element: &mut finished_inner,
+								};
+								result = p.parse_element_state(&mut IgnoreElement);
+							}
+						}
+						self.driver.leave_element();
+						self.driver.mark_element(mark, &tag, Some(index));
+						result.map_err(|e| e.in_element(&tag, Some(index)))?;
+					},
+					Node::PI(content) => {
+						self.driver.position = position;
+						let pi = ProcessingInstruction::from_content(content);
+						if let Err(e) = state.parse_element_inner_pi(pi, &mut *self.driver) {
+							self.driver.recover(e.at(position))?;
+						}
+					},
+					// within elements those shouldn't be there
+					Node::Decl(_) => return Err(errors::unexpected_decl().at(position)),
+					Node::DocType(_) => return Err(errors::unexpected_doctype().at(position)),
+					Node::Comment(text) => {
+						self.driver.position = position;
+						if let Err(e) = state.parse_element_inner_comment(text, &mut *self.driver) {
+							self.driver.recover(e.at(position))?;
+						}
+					},
+					// text+cdata
+					Node::Text(t) => {
+						self.driver.position = position;
+						if let Err(e) = state.parse_element_inner_text(t, &mut *self.driver) {
+							self.driver.recover(e.at(position))?;
+						}
+					},
+				}
+			}
+		}
+
+		*self.finished_element = true;
+		state.parse_element_span(Span {
+			start,
+			end: self.driver.source.position(),
+		});
+		Ok(())
+	}
+}
+
+impl<'x, 'd, 'i, R: Source<'i>> ParseContext for PRef<'x, 'd, 'i, R> {
+	fn warn(&mut self, kind: WarningKind) {
+		self.driver.warn(kind);
+	}
+
+	fn resolve_prefix(&self, prefix: &str) -> Option<&str> {
+		self.driver.resolve_prefix(prefix)
+	}
+}
+
+impl<'x, 'd, 'i, R: Source<'i>> ElementParser<'i> for PRef<'x, 'd, 'i, R> {
+	fn parse_element_state<E: ElementState<'i>>(mut self, state: &mut E) -> Result<()> {
+		self.parse(state)
+	}
+
+	fn parse_element_state_or_skip<E: ElementState<'i>>(mut self, state: &mut E) -> Result<()> {
+		// depth including this element (if not empty)
+		let level = self.driver.depth;
+		let result = self.parse(state);
+		if let Err(e) = &result {
+			if e.is_recoverable() {
+				if !self.empty {
+					self.driver.skip_to_level(level)?;
+				}
+				*self.finished_element = true;
+			}
+		}
+		result
+	}
+
+	fn position(&self) -> Option<Position> {
+		Some(self.start)
+	}
+}
+
+/// These tests don't need any I/O; run them with `cargo miri test` to check the adaptors for
+/// undefined behaviour.
+#[cfg(test)]
+mod test {
+	use crate::{
+		parser::{
+			ElementParser,
+			ElementState,
+			ParseContext,
+			ProcessingInstruction,
+			WarningKind,
+		},
+		quick_xml::{
+			Parser,
+			SliceParser,
+		},
+		ErrorKind,
+		Name,
+		Result,
+	};
+	use std::borrow::Cow;
+
+	/// Generic tree of elements
+	#[derive(PartialEq, Eq, Debug, Default)]
+	struct Tree {
+		tag: String,
+		attributes: Vec<(String, String)>,
+		text: String,
+		children: Vec<Tree>,
+	}
+
+	impl<'a> ElementState<'a> for Tree {
+		type Output = Self;
+
+		fn parse_element_start(name: Name<'_>) -> Option<Self> {
+			Some(Self {
+				tag: name.to_string(),
+				..Self::default()
+			})
+		}
+
+		fn parse_element_attribute(
+			&mut self,
+			name: Name<'_>,
+			value: Cow<'a, str>,
+			_context: &mut dyn ParseContext,
+		) -> Result<()> {
+			self.attributes.push((name.to_string(), value.into_owned()));
+			Ok(())
+		}
+
+		fn parse_element_inner_text(
+			&mut self,
+			text: Cow<'a, str>,
+			_context: &mut dyn ParseContext,
+		) -> Result<()> {
+			self.text += text.trim();
+			Ok(())
+		}
+
+		fn parse_element_inner_node<P: ElementParser<'a>>(
+			&mut self,
+			name: Name<'_>,
+			parser: P,
+		) -> Result<()> {
+			let mut child = Self::parse_element_start(name).expect("accepts all tags");
+			parser.parse_element_state(&mut child)?;
+			self.children.push(child.parse_element_finish()?);
+			Ok(())
+		}
+
+		fn parse_element_finish(self) -> Result<Self::Output> {
+			Ok(self)
+		}
+	}
+
+	const NESTED: &str = r#"<a x="1"><b y="2"><c/><d z="&amp;">d<e/>e</d></b><b/>a</a>"#;
+
+	fn tree(tag: &str, attributes: &[(&str, &str)], text: &str, children: Vec<Tree>) -> Tree {
+		Tree {
+			tag: tag.into(),
+			attributes: attributes
+				.iter()
+				.map(|&(k, v)| (k.into(), v.into()))
+				.collect(),
+			text: text.into(),
+			children,
+		}
+	}
+
+	fn nested_result() -> Tree {
+		tree(
+			"a",
+			&[("x", "1")],
+			"a",
+			vec![
+				tree(
+					"b",
+					&[("y", "2")],
+					"",
+					vec![
+						tree("c", &[], "", vec![]),
+						tree("d", &[("z", "&")], "de", vec![tree("e", &[], "", vec![])]),
+					],
+				),
+				tree("b", &[], "", vec![]),
+			],
+		)
+	}
+
+	#[test]
+	fn nested_buffered() {
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(NESTED));
+		let mut buf = Vec::new();
+		let mut p = Parser::new(&mut r, &mut buf);
+		assert_eq!(
+			p.parse_document_for_state::<Tree>().unwrap(),
+			nested_result()
+		);
+	}
+
+	#[test]
+	fn nested_borrowed() {
+		let mut r = quick_xml::Reader::from_str(NESTED);
+		let mut p = SliceParser::new(&mut r);
+		assert_eq!(
+			p.parse_document_for_state::<Tree>().unwrap(),
+			nested_result()
+		);
+	}
+
+	#[test]
+	fn nested_unclosed() {
+		let input = &NESTED[..NESTED.len() - 4];
+		let mut r = quick_xml::Reader::from_str(input);
+		let mut p = SliceParser::new(&mut r);
+		assert!(p.parse_document_for_state::<Tree>().is_err());
+
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(input));
+		let mut buf = Vec::new();
+		let mut p = Parser::new(&mut r, &mut buf);
+		assert!(p.parse_document_for_state::<Tree>().is_err());
+	}
+
+	const NAMESPACED: &str = r#"<a:x xmlns:a="urn:a" xmlns="urn:d" a:k="1" k="2"><y xmlns:b="urn:a"><b:z/></y><a:y xmlns=""><z xml:lang="en"/></a:y></a:x>"#;
+
+	fn namespaced_result() -> Tree {
+		tree(
+			"{urn:a}x",
+			&[("{urn:a}k", "1"), ("k", "2")],
+			"",
+			vec![
+				tree("{urn:d}y", &[], "", vec![tree("{urn:a}z", &[], "", vec![])]),
+				tree(
+					"{urn:a}y",
+					&[],
+					"",
+					vec![tree(
+						"z",
+						&[("{http://www.w3.org/XML/1998/namespace}lang", "en")],
+						"",
+						vec![],
+					)],
+				),
+			],
+		)
+	}
+
+	#[test]
+	fn namespaces() {
+		let mut r = quick_xml::Reader::from_str(NAMESPACED);
+		let mut p = SliceParser::new(&mut r);
+		assert_eq!(
+			p.parse_document_for_state::<Tree>().unwrap(),
+			namespaced_result()
+		);
+
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(NAMESPACED));
+		let mut buf = Vec::new();
+		let mut p = Parser::new(&mut r, &mut buf);
+		assert_eq!(
+			p.parse_document_for_state::<Tree>().unwrap(),
+			namespaced_result()
+		);
+
+		// declarations are scoped
+		let mut r = quick_xml::Reader::from_str(r#"<a><b:x xmlns:b="urn:b"/><b:y/></a>"#);
+		let mut p = SliceParser::new(&mut r);
+		let e = p.parse_document_for_state::<Tree>().unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::UnboundPrefix { prefix } if prefix == "b"));
+		assert_eq!(e.position().unwrap().column, 26);
+	}
+
+	/// Records comments and processing instructions
+	#[derive(Default)]
+	struct Misc(Vec<String>);
+
+	impl<'a> ElementState<'a> for Misc {
+		type Output = Vec<String>;
+
+		fn parse_element_start(_name: Name<'_>) -> Option<Self> {
+			Some(Self::default())
+		}
+
+		fn parse_element_inner_comment(
+			&mut self,
+			text: Cow<'a, str>,
+			_context: &mut dyn ParseContext,
+		) -> Result<()> {
+			self.0.push(format!("comment {:?}", text));
+			Ok(())
+		}
+
+		fn parse_element_inner_pi(
+			&mut self,
+			pi: ProcessingInstruction<'a>,
+			_context: &mut dyn ParseContext,
+		) -> Result<()> {
+			self.0.push(format!("pi {} {:?}", pi.target, pi.data));
+			Ok(())
+		}
+
+		fn parse_element_finish(self) -> Result<Self::Output> {
+			Ok(self.0)
+		}
+	}
+
+	#[test]
+	fn comments_and_pis() {
+		const INPUT: &str = "<!-- prolog --><a><!-- c1 --><?page-break?><?php echo 1; ?></a>";
+		let expected = [
+			"comment \" c1 \"",
+			"pi page-break \"\"",
+			"pi php \"echo 1; \"",
+		];
+
+		let mut r = quick_xml::Reader::from_str(INPUT);
+		let mut p = SliceParser::new(&mut r);
+		assert_eq!(p.parse_document_for_state::<Misc>().unwrap(), expected);
+
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(INPUT));
+		let mut buf = Vec::new();
+		let mut p = Parser::new(&mut r, &mut buf);
+		assert_eq!(p.parse_document_for_state::<Misc>().unwrap(), expected);
+
+		// rejected by default
+		let mut r = quick_xml::Reader::from_str(INPUT);
+		let mut p = SliceParser::new(&mut r);
+		let e = p.parse_document_for_state::<Tree>().unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::UnexpectedPI));
+		assert_eq!(e.path().to_string(), "/a");
+		assert_eq!(e.position().unwrap().column, 30);
+	}
+
+	/// Skips processing instructions with a warning
+	struct SkipPI;
+
+	impl<'a> ElementState<'a> for SkipPI {
+		type Output = ();
+
+		fn parse_element_start(_name: Name<'_>) -> Option<Self> {
+			Some(Self)
+		}
+
+		fn parse_element_inner_pi(
+			&mut self,
+			pi: ProcessingInstruction<'a>,
+			context: &mut dyn ParseContext,
+		) -> Result<()> {
+			context.warn(WarningKind::IgnoredText {
+				text: format!("<?{} {}?>", pi.target, pi.data),
+			});
+			Ok(())
+		}
+
+		fn parse_element_finish(self) -> Result<Self::Output> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn pi_warnings() {
+		let mut r = quick_xml::Reader::from_str("<a>\n<?php echo 1;?></a>");
+		let mut p = SliceParser::new(&mut r);
+		p.parse_document_for_state::<SkipPI>().unwrap();
+		let warnings: Vec<_> = p.warnings().iter().map(ToString::to_string).collect();
+		assert_eq!(
+			warnings,
+			["line 2, column 1: /a: Ignored text: \"<?php echo 1;?>\""]
+		);
+	}
+}
@@ -0,0 +1,284 @@
+use quick_xml::events::{
+	BytesStart,
+	BytesText,
+	Event,
+};
+
+/// Formatting options of the quick-xml [`Serializer`][`super::Serializer`]
+///
+/// The default writes everything on one line (no whitespace is added at all).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Format {
+	/// Indentation per nesting level; `None` disables pretty-printing
+	///
+	/// Elements with text content (or `xml:space="preserve"`) are written as is: no whitespace
+	/// is added inside them (including their descendants).
+	pub indent: Option<Indent>,
+	/// Line break written between indented nodes (and wrapped attributes)
+	pub newline: Newline,
+	/// Write elements without content as `<a/>` (otherwise as `<a></a>`)
+	pub self_close: bool,
+	/// Put each attribute on its own line if the (indented) start tag would be longer than this
+	/// many bytes; only used with [`Format::indent`]
+	pub wrap_attributes: Option<usize>,
+}
+
+impl Format {
+	/// Indent with two spaces, self-close empty elements, don't wrap attributes
+	pub fn pretty() -> Self {
+		Self {
+			indent: Some(Indent::Spaces(2)),
+			..Self::default()
+		}
+	}
+}
+
+impl Default for Format {
+	fn default() -> Self {
+		Self {
+			indent: None,
+			newline: Newline::Lf,
+			self_close: true,
+			wrap_attributes: None,
+		}
+	}
+}
+
+/// Indentation per nesting level
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Indent {
+	/// Given number of spaces
+	Spaces(usize),
+	/// Given number of tabs
+	Tabs(usize),
+}
+
+impl Indent {
+	fn push(self, buf: &mut String, depth: usize) {
+		let (c, width) = match self {
+			Self::Spaces(width) => (' ', width),
+			Self::Tabs(width) => ('\t', width),
+		};
+		buf.extend(std::iter::repeat_n(c, width * depth));
+	}
+}
+
+/// Line break style
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Newline {
+	/// `\n`
+	#[default]
+	Lf,
+	/// `\r\n`
+	CrLf,
+}
+
+impl Newline {
+	/// Line break as string
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Lf => "\n",
+			Self::CrLf => "\r\n",
+		}
+	}
+}
+
+/// Escaping policy of the quick-xml [`Serializer`][`super::Serializer`] for text and attribute
+/// values
+///
+/// `<` and `&` are always escaped, and so is the quote delimiting attribute values.  Comments,
+/// CDATA sections, processing instructions and names can't contain character references and are
+/// never escaped.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Escape {
+	/// Escape `>` as `&gt;`
+	///
+	/// A `>` following `]]` in text is always escaped (`]]>` isn't allowed in text).
+	pub greater_than: bool,
+	/// Escape both `"` and `'` (`&quot;` and `&apos;`), not only the attribute quote
+	pub quotes: bool,
+	/// Write tab, newline and carriage return in attribute values as character references
+	/// (`&#9;`, `&#10;`, `&#13;`), so they aren't normalized to spaces when parsed
+	pub attribute_whitespace: bool,
+	/// Quote of attribute values
+	pub quote: Quote,
+	/// Write non-ASCII characters as character references (`&#xE9;`)
+	pub ascii_only: bool,
+}
+
+impl Default for Escape {
+	fn default() -> Self {
+		Self {
+			greater_than: true,
+			quotes: true,
+			attribute_whitespace: false,
+			quote: Quote::Double,
+			ascii_only: false,
+		}
+	}
+}
+
+/// Quote of attribute values
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Quote {
+	/// `"`
+	#[default]
+	Double,
+	/// `'`
+	Single,
+}
+
+impl Quote {
+	/// Quote as character
+	pub fn as_char(self) -> char {
+		match self {
+			Self::Double => '"',
+			Self::Single => '\'',
+		}
+	}
+}
+
+/// Whether start tag has `xml:space="preserve"`
+fn preserves_space(start: &BytesStart<'_>) -> bool {
+	start
+		.attributes()
+		.with_checks(false)
+		.flatten()
+		.any(|a| a.key == b"xml:space" && &*a.value == b"preserve")
+}
+
+/// Break line and indent to `depth`
+fn line_break(indent: Indent, format: &Format, depth: usize) -> String {
+	let mut ws = String::from(format.newline.as_str());
+	indent.push(&mut ws, depth);
+	ws
+}
+
+/// Put each attribute of a long start tag on its own line
+fn wrap_attributes(
+	start: BytesStart<'static>,
+	indent: Indent,
+	format: &Format,
+	quote: Quote,
+	depth: usize,
+) -> BytesStart<'static> {
+	let width = match format.wrap_attributes {
+		Some(width) => width,
+		None => return start,
+	};
+	let mut prefix = String::new();
+	indent.push(&mut prefix, depth);
+	// `<` + content + `/>`
+	let attributes: Vec<_> = start.attributes().with_checks(false).flatten().collect();
+	if attributes.len() < 2 || prefix.len() + start.len() + 3 <= width {
+		return start;
+	}
+	let name_len = start.name().len();
+	let mut content = start.name().to_vec();
+	let ws = line_break(indent, format, depth + 1);
+	let quote = quote.as_char() as u8;
+	for attribute in attributes {
+		content.extend_from_slice(ws.as_bytes());
+		content.extend_from_slice(attribute.key);
+		content.extend_from_slice(&[b'=', quote]);
+		content.extend_from_slice(&attribute.value);
+		content.push(quote);
+	}
+	BytesStart::owned(content, name_len)
+}
+
+/// Per index of start event: whether element has text content (or preserves whitespace), and
+/// whether it has child nodes
+fn content(events: &[Event<'static>]) -> (Vec<bool>, Vec<bool>) {
+	let mut mixed = vec![false; events.len()];
+	let mut children = vec![false; events.len()];
+	let mut open = Vec::new();
+	for (ndx, event) in events.iter().enumerate() {
+		let parent = open.last().copied();
+		match event {
+			Event::Text(_) | Event::CData(_) => {
+				if let Some(parent) = parent {
+					mixed[parent] = true;
+				}
+				continue;
+			},
+			Event::End(_) => {
+				open.pop();
+				continue;
+			},
+			Event::Start(start) => {
+				mixed[ndx] = preserves_space(start);
+				open.push(ndx);
+			},
+			_ => (),
+		}
+		if let Some(parent) = parent {
+			children[parent] = true;
+		}
+	}
+	(mixed, children)
+}
+
+/// Whether the element started by the first event (and not closed) has text content, and
+/// whether it has child nodes so far
+pub(super) fn open_content(events: &[Event<'static>]) -> (bool, bool) {
+	let (mixed, children) = content(events);
+	(mixed[0], children[0])
+}
+
+/// Insert indentation into events of an element (and its content) written at nesting level
+/// `depth`
+///
+/// `at_start`: nothing was written before; doesn't break the line before the element.
+pub(super) fn layout(
+	format: &Format,
+	quote: Quote,
+	events: Vec<Event<'static>>,
+	depth: usize,
+	at_start: bool,
+) -> Vec<Event<'static>> {
+	let indent = match format.indent {
+		Some(indent) => indent,
+		None => return events,
+	};
+	let (mixed, children) = content(&events);
+	let mut result = Vec::with_capacity(events.len() * 2);
+	// start event index and whether to indent inside
+	let mut open: Vec<(usize, bool)> = Vec::new();
+	for (ndx, event) in events.into_iter().enumerate() {
+		let indented = open.last().is_none_or(|&(_, inside)| inside);
+		let level = depth + open.len();
+		let ws = match &event {
+			Event::End(_) => {
+				let (start, inside) = open.pop().expect("balanced events");
+				if inside && children[start] {
+					Some(line_break(indent, format, level - 1))
+				} else {
+					None
+				}
+			},
+			Event::Start(_) | Event::Empty(_) | Event::Comment(_) | Event::PI(_) if indented => {
+				if at_start && ndx == 0 {
+					None
+				} else {
+					Some(line_break(indent, format, level))
+				}
+			},
+			_ => None,
+		};
+		if let Some(ws) = ws {
+			result.push(Event::Text(BytesText::from_escaped_str(ws)));
+		}
+		result.push(match event {
+			Event::Start(start) => {
+				open.push((ndx, indented && !mixed[ndx]));
+				Event::Start(wrap_attributes(start, indent, format, quote, level))
+			},
+			Event::Empty(start) => {
+				Event::Empty(wrap_attributes(start, indent, format, quote, level))
+			},
+			event => event,
+		});
+	}
+	result
+}
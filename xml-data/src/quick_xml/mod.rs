@@ -1,15 +1,51 @@
 //! Parser/serializer adaptors using `quick-xml`
 
+mod driver;
+mod format;
 mod parser;
+mod push;
 mod serializer;
+mod slice_parser;
+#[cfg(feature = "tokio")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "tokio")))]
+pub mod tokio;
 
 /// Re-export `quick-xml` crate
 pub use quick_xml;
 
+use crate::errors;
+
 pub use self::{
-	parser::Parser,
+	driver::UnmatchedChildren,
+	format::{
+		Escape,
+		Format,
+		Indent,
+		Newline,
+		Quote,
+	},
+	parser::{
+		Children,
+		Parser,
+	},
+	push::{
+		Progress,
+		PushParser,
+	},
 	serializer::{
 		serialize_document,
+		serialize_full_document,
+		DocumentWriter,
 		Serializer,
 	},
+	slice_parser::SliceParser,
 };
+
+impl From<quick_xml::Error> for crate::Error {
+	fn from(e: quick_xml::Error) -> Self {
+		match e {
+			quick_xml::Error::Io(e) => errors::io(e),
+			e => errors::syntax(e),
+		}
+	}
+}
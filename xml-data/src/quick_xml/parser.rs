@@ -1,195 +1,327 @@
 use crate::{
-	errors,
-	extensions::*,
 	parser::{
 		Element,
-		ElementParser,
 		ElementState,
+		IgnoreElement,
+		Warning,
 	},
+	quick_xml::driver::{
+		self,
+		Attribute,
+		Node,
+		Source,
+		Start,
+		Stream,
+		UnmatchedChildren,
+	},
+	Document,
+	Error,
+	Position,
 	Result,
 };
-use quick_xml::events::Event;
-use std::io::BufRead;
+use quick_xml::events::{
+	BytesStart,
+	Event,
+};
+use std::{
+	io::BufRead,
+	marker::PhantomData,
+};
 
 /// Parser adaptor for `quick_xml::Reader`
+///
+/// As the parser can't borrow from the input all text is passed as owned data.
+///
+/// Line breaks in whitespace skipped by the reader (see `quick_xml::Reader::trim_text`) aren't
+/// seen by the adaptor; line numbers in positions will be off if trimming is enabled.
 pub struct Parser<'a, 'r, B: BufRead> {
 	inner: &'r mut quick_xml::Reader<B>,
-	buf: &'a mut Vec<u8>, // buf is used for pending; must not be touched if pending isn't None.
-	pending: Option<Event<'a>>,
+	buf: &'a mut Vec<u8>, // only used while reading a single event
+	position: Position,   // after the last event
+	skip: usize,          // reader input not part of the document
+	start: usize,         // offset in the document after `skip`
+	collect_errors: bool,
+	warnings: Vec<Warning>, // from the last parsed document
 }
 
 impl<'a, 'r, B: BufRead> Parser<'a, 'r, B> {
 	/// Create a new adaptor from a reader and a buffer
+	///
+	/// Positions start counting at line 1 from the current position of the reader.
 	pub fn new(inner: &'r mut quick_xml::Reader<B>, buf: &'a mut Vec<u8>) -> Self {
+		let position = Position {
+			offset: inner.buffer_position(),
+			..Position::START
+		};
 		Self {
 			inner,
 			buf,
-			pending: None,
+			position,
+			skip: 0,
+			start: 0,
+			collect_errors: false,
+			warnings: Vec::new(),
+		}
+	}
+
+	/// Continue parsing a document at `position` with a (new) reader for the rest of the input
+	///
+	/// Input the reader already read isn't part of the document.
+	pub(super) fn resume(
+		inner: &'r mut quick_xml::Reader<B>,
+		buf: &'a mut Vec<u8>,
+		position: Position,
+	) -> Self {
+		let skip = inner.buffer_position();
+		Self {
+			position,
+			skip,
+			start: position.offset,
+			..Self::new(inner, buf)
 		}
 	}
 
-	fn shift(&mut self) -> Result<()> {
-		self.pending = None; // release buf reference
-		let buf: &'static mut Vec<u8> = unsafe { &mut *(self.buf as *mut _) };
-		self.pending = Some(self.inner.read_event(buf)?);
-		Ok(())
+	/// Offset in the document after the last byte read
+	fn offset(&self) -> usize {
+		self.start + self.inner.buffer_position() - self.skip
+	}
+
+	/// Enable or disable collecting errors
+	///
+	/// When collecting errors, recoverable errors (see [`Error::is_recoverable`]) are recorded and
+	/// parsing continues (skipping elements that failed to parse); if any error was recorded
+	/// parsing fails with [`ErrorKind::Multiple`][`crate::ErrorKind::Multiple`] containing all of
+	/// them.
+	pub fn set_collect_errors(&mut self, collect_errors: bool) {
+		self.collect_errors = collect_errors;
+	}
+
+	/// Warnings recorded while parsing the last document
+	///
+	/// States record warnings when they ignore data (see
+	/// [`ParseContext::warn`][`crate::parser::ParseContext::warn`]), e.g. for
+	/// `#[xml_data(ignore_unknown)]`.
+	pub fn warnings(&self) -> &[Warning] {
+		&self.warnings
+	}
+
+	/// Take warnings recorded while parsing the last document
+	pub fn take_warnings(&mut self) -> Vec<Warning> {
+		std::mem::take(&mut self.warnings)
 	}
 
-	fn peek(&mut self) -> Result<Event<'a>> {
-		if self.pending.is_none() {
-			self.shift()?;
+	/// Update position after reading an event from the buffer; returns start of the event
+	fn track(&mut self, markup: bool) -> Position {
+		let end = self.offset();
+		// markup is enclosed in `<` and `>` (which are not in the buffer)
+		let len = if markup {
+			self.buf.len() + 2
+		} else {
+			self.buf.len()
+		};
+		self.position.skip_to(end.saturating_sub(len));
+		let start = self.position;
+		if markup {
+			self.position.advance(b"<");
 		}
-		Ok(self.pending.clone().expect("can't be None"))
+		self.position.advance(self.buf);
+		self.position.skip_to(end);
+		start
 	}
 
-	fn clear(&mut self) {
-		self.pending = None;
+	/// Position of a read error
+	fn error_position(&self) -> Position {
+		let end = self.offset();
+		let mut position = self.position;
+		let len = self.buf.len().min(end.saturating_sub(position.offset));
+		position.advance(&self.buf[..len]);
+		position.skip_to(end);
+		position
 	}
 
 	/// Parse a single (root) element from reading a document
 	///
 	/// Uses the default state type for the returned element.
-	pub fn parse_document<E: Element>(&mut self) -> Result<E> {
+	///
+	/// As the parser can't borrow from the input all text is passed as owned data, and the result
+	/// can have any lifetime.
+	pub fn parse_document<'i, E: Element<'i>>(&mut self) -> Result<E> {
 		self.parse_document_for_state::<E::ParseState>()
 	}
 
 	/// Parse a single (root) element from reading a document
 	///
 	/// Uses the given state type.
-	pub fn parse_document_for_state<S: ElementState>(&mut self) -> Result<S::Output> {
-		let mut output = None;
-		loop {
-			match self.peek()? {
-				Event::Eof => {
-					if let Some(o) = output {
-						return Ok(o);
-					}
-					return Err(errors::unexpected_eof("empty document"));
-				},
-				Event::End(_) => {
-					return Err(errors::unexpected_end());
-				},
-				Event::Start(s) | Event::Empty(s) => {
-					let tag = self.inner.decode(s.name());
-					let mut finished_inner = false;
-					let p = PRef {
-						parser: self,
-						finished_element: &mut finished_inner,
-					};
-					output = Some(p.parse_element::<S>(&tag)?);
-					if !finished_inner {
-						return Err(errors::inner_element_not_parsed(&tag));
-					}
-					continue;
-				},
-				// not supported
-				Event::PI(_) => return Err(errors::unexpected_pi()),
-				// ignore those at document level before the root element
-				Event::Decl(_) => {
-					if output.is_some() {
-						return Err(errors::unexpected_decl());
-					}
-				},
-				Event::DocType(_) => {
-					if output.is_some() {
-						return Err(errors::unexpected_doctype());
-					}
-				},
-				// ignore comments
-				Event::Comment(_) => (),
-				// text+cdata
-				Event::Text(t) | Event::CData(t) => {
-					let t = t.unescape_and_decode(self.inner)?;
-					if !t.trim().is_empty() {
-						return Err(errors::unexpected_text());
-					}
-				},
-			}
-			// Start+Empty continue directly; everything else needs to be cleared so we don't read it again
-			self.clear();
-		}
+	pub fn parse_document_for_state<'i, S: ElementState<'i>>(&mut self) -> Result<S::Output> {
+		let collect_errors = self.collect_errors;
+		let mut warnings = Vec::new();
+		let result = driver::parse_document::<_, S>(self, collect_errors, None, &mut warnings);
+		self.warnings = warnings;
+		result
+	}
+
+	/// Parse a full document: the root element and everything around it
+	///
+	/// Uses the default state type for the root element.
+	pub fn parse_full_document<'i, E: Element<'i>>(&mut self) -> Result<Document<E>> {
+		self.parse_full_document_for_state::<E::ParseState>()
+	}
+
+	/// Parse a full document: the root element and everything around it
+	///
+	/// Uses the given state type for the root element.
+	pub fn parse_full_document_for_state<'i, S: ElementState<'i>>(
+		&mut self,
+	) -> Result<Document<S::Output>> {
+		let collect_errors = self.collect_errors;
+		let mut document = Document::default();
+		let mut warnings = Vec::new();
+		let result = driver::parse_document::<_, S>(
+			self,
+			collect_errors,
+			Some(&mut document),
+			&mut warnings,
+		);
+		self.warnings = warnings;
+		Ok(document.with_root(result?))
+	}
+
+	/// Parse the children of the root element one at a time (e.g. for huge documents)
+	///
+	/// Reads up to the start tag of the root element (ignoring its attributes); the returned
+	/// iterator parses the next child `E` accepts on each call, handling other children according
+	/// to `unmatched`.  Only the child currently parsed is kept in memory.
+	///
+	/// Warnings are recorded while iterating (see [`Parser::warnings`]).  After a recoverable error
+	/// iteration continues with the next child.
+	pub fn stream_children<'p, 'i, E: Element<'i>>(
+		&'p mut self,
+		unmatched: UnmatchedChildren,
+	) -> Result<Children<'p, 'a, 'r, 'i, B, E::ParseState>> {
+		let (_, children) = self.stream_children_for_state(|_| Some(IgnoreElement), unmatched)?;
+		Ok(children)
+	}
+
+	/// Like [`Parser::stream_children`], but parses the attributes of the root element into the
+	/// header `H`
+	///
+	/// `H` only sees the attributes; it must not require any content.
+	pub fn stream_children_with_header<'p, 'i, H: Element<'i>, E: Element<'i>>(
+		&'p mut self,
+		unmatched: UnmatchedChildren,
+	) -> Result<(H, Children<'p, 'a, 'r, 'i, B, E::ParseState>)> {
+		self.stream_children_for_state(H::ParseState::parse_element_start, unmatched)
+	}
+
+	fn stream_children_for_state<'p, 'i, H: ElementState<'i>, S: ElementState<'i>>(
+		&'p mut self,
+		header: impl FnOnce(crate::Name<'_>) -> Option<H>,
+		unmatched: UnmatchedChildren,
+	) -> Result<(H::Output, Children<'p, 'a, 'r, 'i, B, S>)> {
+		let mut warnings = Vec::new();
+		let result = driver::start_stream(self, header, unmatched, &mut warnings);
+		self.warnings = warnings;
+		let (header, stream) = result?;
+		let children = Children {
+			parser: self,
+			stream,
+			state: PhantomData,
+		};
+		Ok((header, children))
 	}
 }
 
-struct PRef<'x, 'a, 'r, B: BufRead> {
-	parser: &'x mut Parser<'a, 'r, B>,
-	finished_element: &'x mut bool,
+/// Iterator over the parsed children of the root element (see [`Parser::stream_children`])
+pub struct Children<'p, 'a, 'r, 'i, B: BufRead, S> {
+	parser: &'p mut Parser<'a, 'r, B>,
+	stream: Stream,
+	state: PhantomData<fn() -> (S, &'i ())>,
 }
 
-impl<'x, 'a, 'r, B: BufRead> ElementParser for PRef<'x, 'a, 'r, B> {
-	fn parse_element_state<E: ElementState>(self, state: &mut E) -> Result<()> {
-		let (start, closed) = match self.parser.peek()? {
-			Event::Start(s) => (s, false),
-			Event::Empty(s) => (s, true),
-			_ => panic!("Element::read requires start or empty event"),
-		};
+impl<'p, 'a, 'r, 'i, B: BufRead, S: ElementState<'i>> Iterator for Children<'p, 'a, 'r, 'i, B, S> {
+	type Item = Result<S::Output>;
 
-		// TODO: quick-xml decoding sucks. no proper handling, "encoding" feature breaks API.
-		// improve quick-xml, then use it here
+	fn next(&mut self) -> Option<Self::Item> {
+		let mut warnings = Vec::new();
+		let item = driver::next_child::<_, S>(self.parser, &mut self.stream, &mut warnings);
+		self.parser.warnings.extend(warnings);
+		item
+	}
+}
 
-		for attr in start.attributes() {
-			let attr = attr?;
-			let attr_key = self.parser.inner.decode(attr.key);
-			let attr_value = attr.unescape_and_decode_value(self.parser.inner)?;
-			state.parse_element_attribute(&attr_key, attr_value.into())?;
-		}
+fn start<'i, B: BufRead>(
+	reader: &quick_xml::Reader<B>,
+	start: &BytesStart<'_>,
+	empty: bool,
+) -> Result<Start<'i>> {
+	// TODO: quick-xml decoding sucks. no proper handling, "encoding" feature breaks API.
+	// improve quick-xml, then use it here
+	let tag = reader.decode(start.name()).into_owned().into();
+	let attributes = start
+		.attributes()
+		.map(|attr| {
+			let attr = attr.map_err(quick_xml::Error::InvalidAttr)?;
+			let key = reader.decode(attr.key).into_owned().into();
+			let value = attr.unescape_and_decode_value(reader)?.into();
+			Ok(Attribute {
+				key,
+				namespace: None,
+				value,
+			})
+		})
+		.collect::<Result<_>>()?;
+	Ok(Start {
+		tag,
+		namespace: None,
+		attributes,
+		empty,
+	})
+}
 
-		self.parser.clear(); // consume start tag
+fn node<'i, B: BufRead>(reader: &quick_xml::Reader<B>, event: Event<'_>) -> Result<Node<'i>> {
+	Ok(match event {
+		Event::Start(s) => Node::Start(start(reader, &s, false)?),
+		Event::Empty(s) => Node::Start(start(reader, &s, true)?),
+		Event::End(_) => Node::End,
+		Event::Text(t) => Node::Text(t.unescape_and_decode(reader)?.into()),
+		Event::CData(t) => Node::Text(reader.decode(&t).into_owned().into()),
+		Event::Comment(t) => Node::Comment(reader.decode(&t).into_owned().into()),
+		Event::PI(t) => Node::PI(reader.decode(&t).into_owned().into()),
+		Event::Decl(d) => Node::Decl(driver::declaration(reader, &d)?),
+		Event::DocType(t) => Node::DocType(reader.decode(&t).into_owned().into()),
+		Event::Eof => Node::Eof,
+	})
+}
 
-		// read inner (unless there is no inner)
-		if closed {
-			*self.finished_element = true;
-			return Ok(());
-		}
+impl<'i, 'a, 'r, B: BufRead> Source<'i> for Parser<'a, 'r, B> {
+	fn read_node(&mut self) -> Result<(Position, Node<'i>)> {
+		self.buf.clear();
+		let (markup, node) = match self.inner.read_event(self.buf) {
+			Ok(event) => (
+				!matches!(event, Event::Text(_) | Event::Eof),
+				node(self.inner, event),
+			),
+			Err(e) => return Err(Error::from(e).at(self.error_position())),
+		};
+		let start = self.track(markup);
+		Ok((start, node.map_err(|e| e.at(start))?))
+	}
 
-		loop {
-			match self.parser.peek()? {
-				Event::Eof => return Err(errors::unexpected_eof("unclosed element")),
-				Event::End(_) => {
-					self.parser.clear();
-					*self.finished_element = true;
-					return Ok(());
-				},
-				Event::Start(s) | Event::Empty(s) => {
-					let tag = self.parser.inner.decode(s.name());
-					let mut finished_inner = false;
-					let p = PRef {
-						parser: self.parser,
-						finished_element: &mut finished_inner,
-					};
-					state.parse_element_inner_node(&tag, p)?;
-					if !finished_inner {
-						return Err(errors::inner_element_not_parsed(&tag));
-					}
-					continue;
-				},
-				// not supported
-				Event::PI(_) => return Err(errors::unexpected_pi()),
-				// within elements those shouldn't be there
-				Event::Decl(_) => return Err(errors::unexpected_decl()),
-				Event::DocType(_) => return Err(errors::unexpected_doctype()),
-				// ignore comments
-				Event::Comment(_) => (),
-				// text+cdata
-				Event::Text(t) | Event::CData(t) => {
-					let t = t.unescape_and_decode(self.parser.inner)?;
-					state.parse_element_inner_text(t.into())?;
-				},
-			}
-			// Start+Empty continue directly; everything else needs to be cleared so we don't read it again
-			self.parser.clear();
-		}
+	fn position(&self) -> Position {
+		self.position
 	}
 }
 
 #[cfg(test)]
 mod test {
 	use crate::{
+		parser::WarningKind,
 		test_struct::*,
+		ErrorKind,
 		Result,
 	};
 
-	fn parse<T: super::Element>(input: &str) -> Result<T> {
+	fn parse<T: super::Element<'static>>(input: &str) -> Result<T> {
 		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(input));
 		let mut buf = Vec::new();
 		let mut p = super::Parser::new(&mut r, &mut buf);
@@ -202,5 +334,436 @@ mod test {
 			parse::<Data>(Data::TEST_PARSE_DOCUMENT_1).unwrap(),
 			Data::TEST_RESULT_1,
 		);
+		assert!(matches!(
+			parse::<Data>(r#"<data other="1"/>"#).unwrap_err().kind(),
+			ErrorKind::MissingAttribute { key } if key == "key"
+		));
+		assert!(matches!(
+			parse::<Data>(r#"<data key="" other="x"/>"#)
+				.unwrap_err()
+				.kind(),
+			ErrorKind::InvalidValue(_)
+		));
+	}
+
+	#[test]
+	fn test_ignore_unknown() {
+		// unknown elements are skipped with all their content
+		assert_eq!(
+			parse::<Foo>("<foo a='1'><x b='2'>text<y/></x>more</foo>").unwrap(),
+			Foo
+		);
+		assert!(matches!(
+			parse::<Item>(r#"<item value="1"><x/></item>"#)
+				.unwrap_err()
+				.kind(),
+			ErrorKind::UnexpectedElement { tag, .. } if tag == "x"
+		));
+	}
+
+	#[test]
+	fn test_flags() {
+		assert_eq!(
+			parse::<Flags>(Flags::TEST_PARSE_DOCUMENT_1).unwrap(),
+			Flags::TEST_RESULT_1,
+		);
+		assert_eq!(
+			parse::<Flags>("<flags/>").unwrap(),
+			Flags {
+				disabled: false,
+				hidden: false,
+				enabled: false,
+				debug: false,
+			}
+		);
+		assert!(matches!(
+			parse::<Flags>(r#"<flags hidden="yes"/>"#).unwrap_err().kind(),
+			ErrorKind::InvalidFlagValue { key, value } if key == "hidden" && value == "yes"
+		));
+		assert!(parse::<Flags>(r#"<flags><debug>text</debug></flags>"#).is_err());
+	}
+
+	#[test]
+	fn test_error_path() {
+		assert_eq!(
+			parse::<Items>(Items::TEST_PARSE_DOCUMENT_1).unwrap(),
+			Items::test_result_1(),
+		);
+		let e = parse::<Items>(Items::TEST_PARSE_DOCUMENT_INVALID).unwrap_err();
+		assert_eq!(e.path().to_string(), "/items/item[2]/@value");
+		assert!(e
+			.to_string()
+			.starts_with("line 1, column 25: /items/item[2]/@value: Invalid value"));
+
+		// failing `parse_element_finish`
+		let e = parse::<Items>(r#"<items><item value="1"/><item/></items>"#).unwrap_err();
+		assert_eq!(e.path().to_string(), "/items/item[2]");
+		assert!(matches!(e.kind(), ErrorKind::MissingAttribute { .. }));
+
+		let e = parse::<Data>(r#"<data key="" other="1"><foo/><bar/></data>"#).unwrap_err();
+		assert_eq!(e.path().to_string(), "/data/bar[1]");
+	}
+
+	#[test]
+	fn test_positions() {
+		assert_eq!(
+			parse::<SpannedItems>(SpannedItems::TEST_PARSE_DOCUMENT_1).unwrap(),
+			SpannedItems::test_result_1(),
+		);
+		let e = parse::<SpannedItems>(SpannedItems::TEST_PARSE_DOCUMENT_INVALID).unwrap_err();
+		assert_eq!(
+			e.position(),
+			Some(SpannedItems::TEST_ERROR_POSITION_INVALID)
+		);
+		assert_eq!(
+			e.to_string(),
+			r#"line 3, column 2: /items/item[2]: Missing attribute "value""#
+		);
+		let e = parse::<Items>("<items>\n\t<item value=\"1\">\n</items>").unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::Syntax(_)));
+		assert_eq!(e.position().unwrap().line, 3);
+	}
+
+	#[test]
+	fn test_collect_errors() {
+		let input = r#"<items><item value="x"/><item/><bogus><item/></bogus><item value="3" extra=""/></items>"#;
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(input));
+		let mut buf = Vec::new();
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		p.set_collect_errors(true);
+		let e = p.parse_document::<Items>().unwrap_err();
+		let errors = match e.kind() {
+			ErrorKind::Multiple(errors) => errors,
+			_ => panic!("expected multiple errors: {}", e),
+		};
+		let paths: Vec<_> = errors.iter().map(|e| e.path().to_string()).collect();
+		assert_eq!(
+			paths,
+			[
+				"/items/item[1]/@value",
+				"/items/item[1]",
+				"/items/item[2]",
+				"/items/bogus[1]",
+				"/items/item[3]/@extra",
+			]
+		);
+		assert!(matches!(errors[0].kind(), ErrorKind::InvalidValue(_)));
+		assert!(matches!(
+			errors[2].kind(),
+			ErrorKind::MissingAttribute { .. }
+		));
+		assert!(matches!(
+			errors[3].kind(),
+			ErrorKind::UnexpectedElement { .. }
+		));
+		assert!(matches!(
+			errors[4].kind(),
+			ErrorKind::UnexpectedAttribute { .. }
+		));
+
+		// fatal errors end parsing
+		let input = r#"<items><item/><item value="1">"#;
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(input));
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		p.set_collect_errors(true);
+		let e = p.parse_document::<Items>().unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::Multiple(errors) if errors.len() == 2));
+
+		let mut r =
+			quick_xml::Reader::from_reader(std::io::Cursor::new(Items::TEST_PARSE_DOCUMENT_1));
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		p.set_collect_errors(true);
+		assert_eq!(p.parse_document::<Items>().unwrap(), Items::test_result_1());
+	}
+
+	#[test]
+	fn test_lenient() {
+		parse::<LenientItems>(LenientItems::TEST_PARSE_DOCUMENT_1)
+			.unwrap()
+			.assert_result_1();
+
+		// fatal errors still fail
+		assert!(parse::<LenientItems>(r#"<items><item value="x">"#).is_err());
+	}
+
+	#[test]
+	fn test_namespaces() {
+		assert_eq!(
+			parse::<Feed>(Feed::TEST_PARSE_DOCUMENT_1).unwrap(),
+			Feed::test_result_1(),
+		);
+		// wrong namespace of the root element
+		let e = parse::<Feed>(r#"<feed xml:lang="en" id="7"/>"#).unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::UnexpectedElement { tag, .. } if tag == "feed"));
+		// unqualified attribute is not in the vendor namespace
+		let e =
+			parse::<Feed>(r#"<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="en" id="7"/>"#)
+				.unwrap_err();
+		assert_eq!(e.path().to_string(), "/feed/@id");
+	}
+
+	#[test]
+	fn test_comment_and_cdata() {
+		assert_eq!(
+			parse::<Script>(Script::TEST_PARSE_DOCUMENT_1).unwrap(),
+			Script::test_result_1(),
+		);
+		// CDATA content is taken literally (not unescaped)
+		assert_eq!(
+			parse::<Script>("<script><![CDATA[a&amp;b]]></script>")
+				.unwrap()
+				.code,
+			"a&amp;b",
+		);
+	}
+
+	#[test]
+	fn test_qname_values() {
+		assert_eq!(
+			parse::<Typed>(Typed::TEST_PARSE_DOCUMENT_1).unwrap(),
+			Typed::test_result_1(),
+		);
+		let e = parse::<Typed>(
+			r#"<value xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="t:Order"/>"#,
+		)
+		.unwrap_err();
+		assert_eq!(e.path().to_string(), "/value/@xsi:type");
+		assert!(matches!(e.kind(), ErrorKind::InvalidValue(_)));
+
+		// unbound prefixes in values are recoverable
+		let input = r#"<q type="zz:Foo" n="x"/>"#;
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(input));
+		let mut buf = Vec::new();
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		p.set_collect_errors(true);
+		let e = p.parse_document::<QualifiedValue>().unwrap_err();
+		let paths: Vec<_> = match e.kind() {
+			ErrorKind::Multiple(errors) => errors.iter().map(|e| e.path().to_string()).collect(),
+			_ => panic!("expected multiple errors: {}", e),
+		};
+		assert_eq!(paths, ["/q/@type", "/q/@n", "/q"]);
+
+		let values = parse::<LenientQualifiedValues>(
+			r#"<values><q type="zz:Foo" n="1"/><q type="Bar" n="2"/></values>"#,
+		)
+		.unwrap()
+		.values;
+		assert_eq!(values.len(), 2);
+		assert!(matches!(
+			values[0].as_ref().unwrap_err().kind(),
+			ErrorKind::InvalidValue(_)
+		));
+		assert_eq!(values[1].as_ref().unwrap().n, 2);
+	}
+
+	#[test]
+	fn test_warnings() {
+		let input = "<data key=\"\" other=\"1\"><foo retries=\"3\">\n\t<retries>5</retries>text</foo></data>";
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(input));
+		let mut buf = Vec::new();
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		p.parse_document::<Data>().unwrap();
+		let warnings: Vec<_> = p.warnings().iter().map(ToString::to_string).collect();
+		assert_eq!(
+			warnings,
+			[
+				"line 1, column 24: /data/foo[1]/@retries: Ignored attribute: retries",
+				"line 2, column 2: /data/foo[1]/retries[1]: Ignored element: retries",
+				"line 2, column 22: /data/foo[1]: Ignored text: \"text\"",
+			]
+		);
+		assert!(matches!(
+			p.take_warnings()[1].kind(),
+			WarningKind::IgnoredElement { tag } if tag == "retries"
+		));
+
+		// nothing ignored
+		let mut r =
+			quick_xml::Reader::from_reader(std::io::Cursor::new(Items::TEST_PARSE_DOCUMENT_1));
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		p.parse_document::<Items>().unwrap();
+		assert!(p.warnings().is_empty());
+	}
+
+	#[test]
+	fn test_inner_warnings() {
+		let mut r =
+			quick_xml::Reader::from_reader(std::io::Cursor::new("<note>\n\tskipped\n</note>"));
+		let mut buf = Vec::new();
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		assert_eq!(p.parse_document::<Note>().unwrap().text.0, 1);
+		let warnings: Vec<_> = p.warnings().iter().map(ToString::to_string).collect();
+		assert_eq!(
+			warnings,
+			["line 1, column 7: /note: Ignored text: \"skipped\""]
+		);
+	}
+
+	#[test]
+	fn test_expected() {
+		let e = parse::<Data>(r#"<data other="1" ky="x"/>"#).unwrap_err();
+		assert!(matches!(
+			e.kind(),
+			ErrorKind::UnexpectedAttribute { key, expected } if key == "ky" && expected == &["key"]
+		));
+		assert_eq!(e.kind().suggestion(), Some("key"));
+
+		let e = parse::<Data>(r#"<data key="" other="1"><fooo/></data>"#).unwrap_err();
+		assert!(matches!(
+			e.kind(),
+			ErrorKind::UnexpectedElement { tag, expected } if tag == "fooo" && expected == &["foo"]
+		));
+		assert!(e.to_string().ends_with(
+			r#"Unexpected element: fooo (did you mean "foo"?); expected one of: "foo""#
+		));
+
+		// `foo1` and `foo2` already parsed
+		let e = parse::<Data>(r#"<data key="" other="1"><foo/><foo/><foo/></data>"#).unwrap_err();
+		assert!(matches!(
+			e.kind(),
+			ErrorKind::UnexpectedElement { expected, .. } if expected.is_empty()
+		));
+
+		let e = parse::<Flags>(r#"<flags><enabled/><enabeld/></flags>"#).unwrap_err();
+		assert!(matches!(
+			e.kind(),
+			ErrorKind::UnexpectedElement { expected, .. } if expected == &["debug"]
+		));
+
+		// missing namespace declaration
+		let e = parse::<Feed>("<feed/>").unwrap_err();
+		assert_eq!(e.kind().suggestion(), None);
+		assert_eq!(
+			e.kind().to_string(),
+			r#"Unexpected element: feed; expected one of: "{http://www.w3.org/2005/Atom}feed""#
+		);
+	}
+
+	#[test]
+	fn test_full_document() {
+		let mut r =
+			quick_xml::Reader::from_reader(std::io::Cursor::new(TEST_PARSE_FULL_DOCUMENT_1));
+		let mut buf = Vec::new();
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		assert_eq!(
+			p.parse_full_document::<Item>().unwrap(),
+			test_full_document_1()
+		);
+
+		// a second DOCTYPE is an error
+		let input = "<!DOCTYPE a><!DOCTYPE b><item value=\"1\"/>";
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(input));
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		let e = p.parse_full_document::<Item>().unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::UnexpectedDocType));
+	}
+
+	const STREAM: &str = concat!(
+		r#"<items count="4"><item value="1"/><!-- c --><other><item value="9"/></other>"#,
+		r#"<item value="x"/> <item value="3"></item></items>"#,
+	);
+
+	fn stream<'i, T: super::Element<'i>>(
+		input: &str,
+		unmatched: super::UnmatchedChildren,
+	) -> (Vec<Result<T>>, Vec<super::Warning>) {
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(input));
+		let mut buf = Vec::new();
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		let items = match p.stream_children::<T>(unmatched) {
+			Ok(children) => children.collect(),
+			Err(e) => vec![Err(e)],
+		};
+		(items, p.take_warnings())
+	}
+
+	#[test]
+	fn test_stream_children() {
+		let (items, warnings) = stream::<Item>(STREAM, super::UnmatchedChildren::Skip);
+		assert!(warnings.is_empty());
+		assert_eq!(items.len(), 3);
+		assert_eq!(items[0].as_ref().unwrap(), &Item { value: 1 });
+		let e = items[1].as_ref().unwrap_err();
+		assert_eq!(e.path().to_string(), "/items/item[2]/@value");
+		assert!(e.is_recoverable());
+		assert_eq!(items[2].as_ref().unwrap(), &Item { value: 3 });
+
+		let (items, warnings) = stream::<Item>(STREAM, super::UnmatchedChildren::Warn);
+		assert_eq!(items.len(), 3);
+		assert_eq!(warnings.len(), 1);
+		assert_eq!(warnings[0].path().to_string(), "/items/other[1]");
+		assert_eq!(
+			warnings[0].kind(),
+			&WarningKind::IgnoredElement {
+				tag: "other".into()
+			}
+		);
+
+		let (items, _) = stream::<Item>(STREAM, super::UnmatchedChildren::Error);
+		assert_eq!(items.len(), 4);
+		let e = items[1].as_ref().unwrap_err();
+		assert_eq!(e.path().to_string(), "/items/other[1]");
+		assert!(matches!(e.kind(), ErrorKind::UnexpectedElement { tag, .. } if tag == "other"));
+
+		let (items, _) = stream::<Item>("<items/>", super::UnmatchedChildren::Skip);
+		assert!(items.is_empty());
+		// fatal errors end the iteration
+		let (items, _) = stream::<Item>(
+			r#"<items><item value="1"/></items><items/>"#,
+			super::UnmatchedChildren::Skip,
+		);
+		assert_eq!(items.len(), 2);
+		assert!(matches!(
+			items[1].as_ref().unwrap_err().kind(),
+			ErrorKind::UnexpectedElement { .. }
+		));
+		let (items, _) = stream::<Item>(
+			r#"<items><item value="1"/>"#,
+			super::UnmatchedChildren::Skip,
+		);
+		assert_eq!(items.len(), 2);
+		assert!(matches!(
+			items[1].as_ref().unwrap_err().kind(),
+			ErrorKind::UnexpectedEof { .. }
+		));
+	}
+
+	#[test]
+	fn test_stream_children_header() {
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new(STREAM));
+		let mut buf = Vec::new();
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		let (header, children) = p
+			.stream_children_with_header::<ItemsHeader, Item>(super::UnmatchedChildren::Skip)
+			.unwrap();
+		assert_eq!(header, ItemsHeader { count: 4 });
+		assert_eq!(children.filter(Result::is_ok).count(), 2);
+
+		let mut r = quick_xml::Reader::from_reader(std::io::Cursor::new("<other/>"));
+		let mut p = super::Parser::new(&mut r, &mut buf);
+		let e = p
+			.stream_children_with_header::<ItemsHeader, Item>(super::UnmatchedChildren::Skip)
+			.err()
+			.unwrap();
+		assert!(matches!(e.kind(), ErrorKind::UnexpectedElement { tag, .. } if tag == "other"));
+	}
+
+	#[test]
+	fn test_stream_children_namespaces() {
+		// declarations of the root element stay in scope between children
+		let input = concat!(
+			r#"<list xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:t="urn:types">"#,
+			r#"<value xsi:type="t:Item"/><value xmlns:t="urn:other" xsi:type="t:Item"/>"#,
+			r#"<value xsi:type="t:Order"/></list>"#,
+		);
+		let (items, _) = stream::<Typed>(input, super::UnmatchedChildren::Skip);
+		let kinds: Vec<String> = items
+			.into_iter()
+			.map(|item| item.unwrap().kind.to_string())
+			.collect();
+		assert_eq!(
+			kinds,
+			["{urn:types}Item", "{urn:other}Item", "{urn:types}Order"]
+		);
 	}
 }
@@ -0,0 +1,618 @@
+use super::{
+	driver::{
+		self,
+		Stream,
+		UnmatchedChildren,
+	},
+	parser::Parser,
+};
+use crate::{
+	errors,
+	parser::{
+		Element,
+		IgnoreElement,
+		Warning,
+	},
+	Position,
+	Result,
+};
+use quick_xml::events::Event;
+use std::{
+	io::{
+		BufRead,
+		Read,
+	},
+	marker::PhantomData,
+};
+
+/// Progress of a [`PushParser`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Progress<T> {
+	/// Parsed a complete element
+	Element(T),
+	/// The input fed so far doesn't complete another element; feed more with
+	/// [`PushParser::feed`], or signal the end of the input with [`PushParser::finish`]
+	NeedData,
+	/// Parsed the whole document
+	Done,
+}
+
+/// Kind of a complete piece of input
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Token {
+	/// Text (including CDATA sections)
+	Text,
+	Start,
+	Empty,
+	End,
+	/// Comments, processing instructions, declaration and DOCTYPE
+	Other,
+}
+
+enum Phase {
+	/// Up to the root element (document mode: including the root element)
+	Root,
+	/// In the root element
+	Children(Stream),
+	/// After the root element
+	Trailing,
+	/// Done or failed
+	Done,
+}
+
+/// Sans-IO parser fed with chunks of bytes
+///
+/// Buffers input until an element is complete and parses it right away; either the root
+/// element ([`PushParser::document`]) or each child of the root element
+/// ([`PushParser::children`]).  Completely parsed input is dropped.
+///
+/// The encoding declared in the document must be ASCII-compatible (e.g. UTF-8 or ISO-8859-1).
+///
+/// Call [`PushParser::poll`] after feeding data until it reports [`Progress::NeedData`].
+pub struct PushParser<E> {
+	buf: Vec<u8>,
+	state: PushState<E>,
+}
+
+impl<E: Element<'static>> PushParser<E> {
+	/// Parse the root element `E` once it is complete
+	///
+	/// Comments are allowed outside the root element; processing instructions are rejected.
+	pub fn document() -> Self {
+		Self {
+			buf: Vec::new(),
+			state: PushState::new(None),
+		}
+	}
+
+	/// Parse each child of the root element as `E` once it is complete
+	///
+	/// Like [`Parser::stream_children`]: the attributes of the root element are ignored, children
+	/// `E` doesn't accept are handled according to `unmatched`, and errors parsing a child are
+	/// recoverable (polling continues with the next child).
+	pub fn children(unmatched: UnmatchedChildren) -> Self {
+		Self {
+			buf: Vec::new(),
+			state: PushState::new(Some(unmatched)),
+		}
+	}
+
+	/// Append input
+	pub fn feed(&mut self, data: &[u8]) {
+		assert!(!self.state.eof, "fed data after finish");
+		self.buf.extend_from_slice(data);
+	}
+
+	/// Signal the end of the input
+	///
+	/// Afterwards [`PushParser::poll`] fails on incomplete input instead of waiting for more.
+	pub fn finish(&mut self) {
+		self.state.finish();
+	}
+
+	/// Warnings recorded so far
+	pub fn warnings(&self) -> &[Warning] {
+		&self.state.warnings
+	}
+
+	/// Take warnings recorded so far
+	pub fn take_warnings(&mut self) -> Vec<Warning> {
+		self.state.take_warnings()
+	}
+
+	/// Whether the document was completely parsed, or parsing failed
+	pub fn is_done(&self) -> bool {
+		self.state.is_done()
+	}
+
+	/// Parse the next element if the input is complete enough
+	///
+	/// Returns [`Progress::Done`] after a fatal error.
+	pub fn poll(&mut self) -> Result<Progress<E>> {
+		let mut consumed = 0;
+		let result = self.state.poll(&self.buf, &mut consumed);
+		self.buf.drain(..consumed);
+		result
+	}
+}
+
+/// State of a [`PushParser`] without the buffered input
+///
+/// The input is passed to [`PushState::poll`]; the caller drops consumed input.
+pub(super) struct PushState<E> {
+	/// Position of the first byte of the input
+	position: Position,
+	/// Length of the input prefix split into complete tokens
+	scanned: usize,
+	/// Open elements after the scanned tokens
+	depth: usize,
+	/// `None` in document mode
+	unmatched: Option<UnmatchedChildren>,
+	/// Encoding declared in the document (`None` for UTF-8)
+	encoding: Option<&'static str>,
+	phase: Phase,
+	eof: bool,
+	warnings: Vec<Warning>,
+	element: PhantomData<fn() -> E>,
+}
+
+impl<E: Element<'static>> PushState<E> {
+	/// Document mode if `unmatched` is `None`, otherwise children mode
+	pub(super) fn new(unmatched: Option<UnmatchedChildren>) -> Self {
+		Self {
+			position: Position::START,
+			scanned: 0,
+			depth: 0,
+			unmatched,
+			encoding: None,
+			phase: Phase::Root,
+			eof: false,
+			warnings: Vec::new(),
+			element: PhantomData,
+		}
+	}
+
+	pub(super) fn finish(&mut self) {
+		self.eof = true;
+	}
+
+	pub(super) fn take_warnings(&mut self) -> Vec<Warning> {
+		std::mem::take(&mut self.warnings)
+	}
+
+	pub(super) fn is_done(&self) -> bool {
+		matches!(self.phase, Phase::Done)
+	}
+
+	/// Parse the next element from `input`; adds the length of the parsed prefix to `consumed`
+	///
+	/// The input must start after the input consumed before.
+	pub(super) fn poll(&mut self, input: &[u8], consumed: &mut usize) -> Result<Progress<E>> {
+		loop {
+			let data = &input[*consumed..];
+			match self.phase {
+				Phase::Done => return Ok(Progress::Done),
+				Phase::Trailing => return self.trailing(input, consumed),
+				_ => (),
+			}
+			let end = match self.scan_unit(data) {
+				Some(end) => end,
+				// let the driver report the problem with the incomplete input
+				None if self.eof => data.len(),
+				None => return Ok(Progress::NeedData),
+			};
+			let unit = &data[..end];
+			let result = self.parse_unit(unit);
+			self.position.advance(unit);
+			*consumed += end;
+			self.scanned -= end;
+			match result {
+				Ok(Some(element)) => return Ok(Progress::Element(element)),
+				Ok(None) => (),
+				Err(e) => {
+					if !matches!(&self.phase, Phase::Children(stream) if !stream.is_done()) {
+						self.phase = Phase::Done;
+					}
+					return Err(e);
+				},
+			}
+		}
+	}
+
+	/// Parse a complete unit; moves on to the next phase when done
+	fn parse_unit(&mut self, unit: &[u8]) -> Result<Option<E>> {
+		// later units need the encoding from the declaration in the first unit
+		let declaration = match self.encoding {
+			Some(encoding) => format!("<?xml version=\"1.0\" encoding=\"{}\"?>", encoding),
+			None => String::new(),
+		};
+		let mut reader = quick_xml::Reader::from_reader(declaration.as_bytes().chain(unit));
+		// end tags of the root element are in a different unit than the start tag
+		reader.check_end_names(false);
+		let mut buf = Vec::new();
+		if !declaration.is_empty() {
+			// the reader starts with an empty text event
+			while !matches!(reader.read_event(&mut buf)?, Event::Decl(_)) {}
+		}
+		let mut parser = Parser::resume(&mut reader, &mut buf, self.position);
+		match (&mut self.phase, self.unmatched) {
+			(Phase::Root, None) => {
+				self.phase = Phase::Trailing;
+				driver::parse_document::<_, E::ParseState>(
+					&mut parser,
+					false,
+					None,
+					&mut self.warnings,
+				)
+				.map(Some)
+			},
+			(Phase::Root, Some(unmatched)) => {
+				let (_, mut stream) = driver::start_stream(
+					&mut parser,
+					|_| Some(IgnoreElement),
+					unmatched,
+					&mut self.warnings,
+				)?;
+				self.encoding = declared_encoding(&reader).map_err(|e| e.at(self.position))?;
+				stream.set_partial(true);
+				// an empty root element doesn't open a level
+				self.phase = if self.depth == 0 {
+					Phase::Trailing
+				} else {
+					Phase::Children(stream)
+				};
+				Ok(None)
+			},
+			(Phase::Children(stream), _) => {
+				stream.set_partial(!self.eof);
+				let result =
+					driver::next_child::<_, E::ParseState>(&mut parser, stream, &mut self.warnings);
+				if stream.is_done() && result.as_ref().is_none_or(|r| r.is_ok()) {
+					self.phase = Phase::Trailing;
+				}
+				result.transpose()
+			},
+			(Phase::Trailing, _) | (Phase::Done, _) => unreachable!(),
+		}
+	}
+
+	/// Scan complete tokens up to the end of the next unit to parse: the root element (document
+	/// mode), the start tag of the root element or a child of the root element (children mode)
+	///
+	/// Returns the end offset of the unit.
+	fn scan_unit(&mut self, data: &[u8]) -> Option<usize> {
+		while self.scanned < data.len() {
+			let (token, end) = token(&data[self.scanned..], self.eof)?;
+			self.scanned += end;
+			// nesting level of the tag (root element: 1)
+			let level = match token {
+				Token::Start => {
+					self.depth += 1;
+					self.depth
+				},
+				Token::End => {
+					let level = self.depth;
+					self.depth = self.depth.saturating_sub(1);
+					level.max(1)
+				},
+				Token::Empty => self.depth + 1,
+				Token::Text | Token::Other => continue,
+			};
+			let complete = match self.phase {
+				Phase::Root if self.unmatched.is_some() => level == 1,
+				Phase::Root => level == 1 && token != Token::Start,
+				Phase::Children(_) => level == 1 || (level == 2 && token != Token::Start),
+				Phase::Trailing | Phase::Done => false,
+			};
+			if complete {
+				return Some(self.scanned);
+			}
+		}
+		None
+	}
+
+	/// Check comments and whitespace after the root element
+	fn trailing(&mut self, input: &[u8], consumed: &mut usize) -> Result<Progress<E>> {
+		while *consumed < input.len() {
+			let (token, end) = match token(&input[*consumed..], self.eof) {
+				Some(token) => token,
+				None if self.eof => {
+					self.phase = Phase::Done;
+					return Err(errors::unexpected_eof("incomplete markup").at(self.position));
+				},
+				None => return Ok(Progress::NeedData),
+			};
+			let data = &input[*consumed..][..end];
+			let error = match token {
+				Token::Text if data.iter().all(u8::is_ascii_whitespace) => None,
+				Token::Other if data.starts_with(b"<!--") => None,
+				Token::Text => Some(errors::unexpected_text()),
+				Token::Start | Token::Empty => {
+					let name = data[1..]
+						.split(|&b| b.is_ascii_whitespace() || b == b'/' || b == b'>')
+						.next()
+						.unwrap_or_default();
+					Some(errors::unexpected_element(&String::from_utf8_lossy(name)))
+				},
+				Token::End => Some(errors::unexpected_end()),
+				Token::Other if data.starts_with(b"<!") => Some(errors::unexpected_doctype()),
+				Token::Other if is_declaration(data) => Some(errors::unexpected_decl()),
+				Token::Other => Some(errors::unexpected_pi()),
+			};
+			if let Some(e) = error {
+				self.phase = Phase::Done;
+				return Err(e.at(self.position));
+			}
+			self.position.advance(data);
+			*consumed += end;
+		}
+		self.scanned = 0;
+		if self.eof {
+			self.phase = Phase::Done;
+			Ok(Progress::Done)
+		} else {
+			Ok(Progress::NeedData)
+		}
+	}
+}
+
+/// Encoding the reader detected, unless UTF-8
+///
+/// Splitting the input into units requires an ASCII-compatible encoding.
+fn declared_encoding<B: BufRead>(reader: &quick_xml::Reader<B>) -> Result<Option<&'static str>> {
+	let encoding = reader.encoding();
+	if !encoding.is_ascii_compatible() {
+		return Err(errors::syntax(format!(
+			"unsupported encoding {} (must be ASCII-compatible)",
+			encoding.name()
+		)));
+	}
+	if encoding.name() == "UTF-8" {
+		Ok(None)
+	} else {
+		Ok(Some(encoding.name()))
+	}
+}
+
+fn is_declaration(data: &[u8]) -> bool {
+	data.starts_with(b"<?xml") && data.get(5).is_some_and(u8::is_ascii_whitespace)
+}
+
+/// Find the end of the complete token at the start of `data`; `None` if more input is needed
+///
+/// Text ends before the next `<` (or at the end of the input if `eof` is set).
+fn token(data: &[u8], eof: bool) -> Option<(Token, usize)> {
+	if data[0] != b'<' {
+		return match data.iter().position(|&b| b == b'<') {
+			Some(end) => Some((Token::Text, end)),
+			None if eof => Some((Token::Text, data.len())),
+			None => None,
+		};
+	}
+	let find = |token: Token, start: usize, pattern: &[u8]| {
+		data[start..]
+			.windows(pattern.len())
+			.position(|w| w == pattern)
+			.map(|pos| (token, start + pos + pattern.len()))
+	};
+	if data.starts_with(b"<!--") {
+		return find(Token::Other, 4, b"-->");
+	}
+	if data.starts_with(b"<![CDATA[") {
+		return find(Token::Text, 9, b"]]>");
+	}
+	if data.starts_with(b"<?") {
+		return find(Token::Other, 2, b"?>");
+	}
+	if b"<!--".starts_with(data) || b"<![CDATA[".starts_with(data) {
+		// can't tell yet
+		return None;
+	}
+	let mut quote = None;
+	if data.starts_with(b"<!") {
+		// DOCTYPE, possibly with internal subset
+		let mut brackets = 0usize;
+		for (ndx, &b) in data.iter().enumerate().skip(2) {
+			match (quote, b) {
+				(Some(q), _) if b == q => quote = None,
+				(Some(_), _) => (),
+				(None, b'"') | (None, b'\'') => quote = Some(b),
+				(None, b'[') => brackets += 1,
+				(None, b']') => brackets = brackets.saturating_sub(1),
+				(None, b'>') if brackets == 0 => return Some((Token::Other, ndx + 1)),
+				_ => (),
+			}
+		}
+		return None;
+	}
+	for (ndx, &b) in data.iter().enumerate().skip(1) {
+		match (quote, b) {
+			(Some(q), _) if b == q => quote = None,
+			(Some(_), _) => (),
+			(None, b'"') | (None, b'\'') => quote = Some(b),
+			(None, b'>') => {
+				let token = if data[1] == b'/' {
+					Token::End
+				} else if data[ndx - 1] == b'/' {
+					Token::Empty
+				} else {
+					Token::Start
+				};
+				return Some((token, ndx + 1));
+			},
+			_ => (),
+		}
+	}
+	None
+}
+
+#[cfg(test)]
+mod test {
+	use super::{
+		Progress,
+		PushParser,
+		UnmatchedChildren,
+	};
+	use crate::{
+		parser::Warning,
+		test_struct::*,
+		ErrorKind,
+		Result,
+	};
+
+	/// Feed input byte by byte, collecting everything polled
+	fn push<E: crate::parser::Element<'static>>(
+		mut parser: PushParser<E>,
+		input: impl AsRef<[u8]>,
+	) -> (Vec<Result<E>>, Vec<Warning>) {
+		let mut items = Vec::new();
+		let mut drain = |parser: &mut PushParser<E>| loop {
+			match parser.poll() {
+				Ok(Progress::Element(e)) => items.push(Ok(e)),
+				Ok(Progress::NeedData) | Ok(Progress::Done) => break,
+				Err(e) => items.push(Err(e)),
+			}
+		};
+		for b in input.as_ref() {
+			parser.feed(std::slice::from_ref(b));
+			drain(&mut parser);
+		}
+		parser.finish();
+		drain(&mut parser);
+		assert!(matches!(parser.poll(), Ok(Progress::Done)));
+		(items, parser.take_warnings())
+	}
+
+	const STREAM: &str = concat!(
+		"<?xml version=\"1.0\"?>\n<!DOCTYPE items [<!ENTITY a \"b\">]>\n",
+		r#"<items count="4"><item value="1"/><!-- c --><other><item value="9"/></other>"#,
+		r#"<item value="x"/> <item value="3"></item></items>"#,
+		"\n<!-- done -->\n",
+	);
+
+	#[test]
+	fn test_children() {
+		let (items, warnings) = push(
+			PushParser::<Item>::children(UnmatchedChildren::Skip),
+			STREAM,
+		);
+		assert!(warnings.is_empty());
+		assert_eq!(items.len(), 3);
+		assert_eq!(items[0].as_ref().unwrap(), &Item { value: 1 });
+		let e = items[1].as_ref().unwrap_err();
+		assert_eq!(e.path().to_string(), "/items/item[2]/@value");
+		let position = e.position().unwrap();
+		assert_eq!((position.line, position.column), (3, 77));
+		assert_eq!(items[2].as_ref().unwrap(), &Item { value: 3 });
+
+		let (items, warnings) = push(
+			PushParser::<Item>::children(UnmatchedChildren::Warn),
+			STREAM,
+		);
+		assert_eq!(items.len(), 3);
+		assert_eq!(warnings.len(), 1);
+		assert_eq!(warnings[0].path().to_string(), "/items/other[1]");
+
+		let (items, _) = push(
+			PushParser::<Item>::children(UnmatchedChildren::Skip),
+			"<items/>",
+		);
+		assert!(items.is_empty());
+	}
+
+	#[test]
+	fn test_encoding() {
+		let input = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<scripts><script>\xe9</script><script><!--\xe0--></script></scripts>";
+		let (items, _) = push(
+			PushParser::<Script>::children(UnmatchedChildren::Skip),
+			input,
+		);
+		assert_eq!(items.len(), 2);
+		assert_eq!(items[0].as_ref().unwrap().code, "\u{e9}");
+		assert_eq!(items[1].as_ref().unwrap().note, "\u{e0}");
+		// positions don't include the replayed declaration
+		let input =
+			b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><items><item value=\"x\"/></items>";
+		let (items, _) = push(PushParser::<Item>::children(UnmatchedChildren::Skip), input);
+		assert_eq!(
+			items[0].as_ref().unwrap_err().position().unwrap().offset,
+			50
+		);
+
+		let input = b"<?xml version=\"1.0\" encoding=\"UTF-16\"?><items/>";
+		let (items, _) = push(PushParser::<Item>::children(UnmatchedChildren::Skip), input);
+		assert!(matches!(
+			items[0].as_ref().unwrap_err().kind(),
+			ErrorKind::Syntax(_)
+		));
+	}
+
+	#[test]
+	fn test_need_data() {
+		let mut parser = PushParser::<Item>::children(UnmatchedChildren::Skip);
+		assert_eq!(parser.poll().unwrap(), Progress::NeedData);
+		parser.feed(br#"<items><item value="1"/><item val"#);
+		assert_eq!(parser.poll().unwrap(), Progress::Element(Item { value: 1 }));
+		assert_eq!(parser.poll().unwrap(), Progress::NeedData);
+		parser.feed(br#"ue="2"/></items>"#);
+		assert_eq!(parser.poll().unwrap(), Progress::Element(Item { value: 2 }));
+		assert_eq!(parser.poll().unwrap(), Progress::NeedData);
+		parser.finish();
+		assert_eq!(parser.poll().unwrap(), Progress::Done);
+	}
+
+	#[test]
+	fn test_document() {
+		let (items, _) = push(
+			PushParser::<Items>::document(),
+			Items::TEST_PARSE_DOCUMENT_1,
+		);
+		assert_eq!(items.len(), 1);
+		assert_eq!(
+			items[0].as_ref().unwrap(),
+			&Items {
+				items: vec![Item { value: 1 }, Item { value: 2 }],
+			}
+		);
+		let (items, _) = push(
+			PushParser::<Item>::document(),
+			"<!-- a --><item value='5'/> ",
+		);
+		assert_eq!(items[0].as_ref().unwrap(), &Item { value: 5 });
+	}
+
+	#[test]
+	fn test_errors() {
+		// unclosed root element
+		let (items, _) = push(
+			PushParser::<Item>::children(UnmatchedChildren::Skip),
+			r#"<items><item value="1"/>"#,
+		);
+		assert_eq!(items.len(), 2);
+		assert!(matches!(
+			items[1].as_ref().unwrap_err().kind(),
+			ErrorKind::UnexpectedEof { .. }
+		));
+
+		// a second root element
+		let (items, _) = push(
+			PushParser::<Item>::children(UnmatchedChildren::Skip),
+			r#"<items><item value="1"/></items> <items/>"#,
+		);
+		assert_eq!(items.len(), 2);
+		let e = items[1].as_ref().unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::UnexpectedElement { tag, .. } if tag == "items"));
+		assert_eq!(e.position().unwrap().offset, 33);
+
+		let (items, _) = push(PushParser::<Item>::document(), "<item value='5'/><?pi?>");
+		assert!(matches!(
+			items[1].as_ref().unwrap_err().kind(),
+			ErrorKind::UnexpectedPI
+		));
+		let (items, _) = push(PushParser::<Item>::document(), "");
+		assert!(matches!(
+			items[0].as_ref().unwrap_err().kind(),
+			ErrorKind::UnexpectedEof { .. }
+		));
+	}
+}
@@ -1,12 +1,32 @@
+use super::format::{
+	self,
+	Escape,
+	Format,
+	Quote,
+};
 use crate::{
+	document::{
+		Declaration,
+		Document,
+		Misc,
+		Version,
+	},
+	errors,
+	namespace::{
+		self,
+		Namespaces,
+	},
 	serializer::{
 		self,
 		Element,
+		Inner,
 	},
+	Name,
 	Result,
 };
 use quick_xml::events::{
 	attributes::Attribute,
+	BytesCData,
 	BytesDecl,
 	BytesEnd,
 	BytesStart,
@@ -15,9 +35,104 @@ use quick_xml::events::{
 };
 use std::{
 	borrow::Cow,
+	collections::HashMap,
 	io,
 };
 
+/// Whether prefix can be bound by a declaration
+fn usable_prefix(prefix: &str) -> bool {
+	namespace::is_ncname(prefix)
+		&& !prefix
+			.get(..3)
+			.is_some_and(|p| p.eq_ignore_ascii_case("xml"))
+}
+
+/// Version of documents with the given declaration
+fn version(declaration: Option<&Declaration>) -> Result<Version> {
+	declaration.map_or(Ok(Version::V1_0), Declaration::xml_version)
+}
+
+/// Whether name is valid as encoding name in the declaration
+fn valid_encoding_name(name: &str) -> bool {
+	name.starts_with(|c: char| c.is_ascii_alphabetic())
+		&& name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn declaration_event(declaration: &Declaration) -> Result<Event<'_>> {
+	if let Some(encoding) = &declaration.encoding {
+		if !valid_encoding_name(encoding) {
+			return Err(errors::invalid_value(format!(
+				"invalid encoding name: {:?}",
+				encoding
+			)));
+		}
+	}
+	let standalone = declaration
+		.standalone
+		.map(|standalone| if standalone { "yes" } else { "no" });
+	Ok(Event::Decl(BytesDecl::new(
+		declaration.version.as_bytes(),
+		declaration.encoding.as_ref().map(String::as_bytes),
+		standalone.map(str::as_bytes),
+	)))
+}
+
+/// Check characters in text (or attribute values) for the XML version and escape them according
+/// to the policy; restricted characters (and `>` ending `]]>` in text) are always escaped
+fn escape<'t>(
+	version: Version,
+	policy: &Escape,
+	text: &'t str,
+	attribute: bool,
+) -> Result<Cow<'t, str>> {
+	let mut escaped = String::new();
+	// end of text already copied to `escaped`
+	let mut copied = 0;
+	for (ndx, c) in text.char_indices() {
+		if !version.is_char(c) {
+			return Err(errors::invalid_character(c));
+		}
+		let quote = |q| policy.quotes || (attribute && policy.quote == q);
+		let whitespace = attribute && policy.attribute_whitespace;
+		let entity = match c {
+			'<' => "&lt;",
+			'&' => "&amp;",
+			// `]]>` isn't allowed in text
+			'>' if policy.greater_than || (!attribute && text[..ndx].ends_with("]]")) => "&gt;",
+			'\'' if quote(Quote::Single) => "&apos;",
+			'"' if quote(Quote::Double) => "&quot;",
+			'\t' if whitespace => "&#9;",
+			'\n' if whitespace => "&#10;",
+			'\r' if whitespace => "&#13;",
+			_ if version.is_restricted(c) || (policy.ascii_only && !c.is_ascii()) => "",
+			_ => continue,
+		};
+		escaped.push_str(&text[copied..ndx]);
+		if entity.is_empty() {
+			escaped.push_str(&format!("&#x{:X};", u32::from(c)));
+		} else {
+			escaped.push_str(entity);
+		}
+		copied = ndx + c.len_utf8();
+	}
+	if copied == 0 {
+		return Ok(Cow::Borrowed(text));
+	}
+	escaped.push_str(&text[copied..]);
+	Ok(Cow::Owned(escaped))
+}
+
+/// Content of processing instruction (between `<?` and `?>`)
+fn pi_content<'t>(target: &'t str, data: &str) -> Cow<'t, str> {
+	if data.is_empty() {
+		Cow::Borrowed(target)
+	} else {
+		Cow::Owned(format!("{} {}", target, data))
+	}
+}
+
 fn cow_bytes(value: Cow<'_, str>) -> Cow<'_, [u8]> {
 	match value {
 		Cow::Owned(v) => Cow::Owned(v.into()),
@@ -35,48 +150,423 @@ pub fn serialize_document<E: Element>(element: &E) -> Result<String> {
 	Ok(String::from_utf8(buf).expect("buffer should be utf-8 clean"))
 }
 
+/// Serialize full document (root element and everything around it) into memory
+pub fn serialize_full_document<E: Element>(document: &Document<E>) -> Result<String> {
+	let mut buf = Vec::new();
+	let mut writer = quick_xml::Writer::new(&mut buf);
+	let mut serializer = Serializer::new(&mut writer);
+	serializer.serialize_full_document(document)?;
+	// there shouldn't be any way to write binary (non-utf8) data to the buffer
+	Ok(String::from_utf8(buf).expect("buffer should be utf-8 clean"))
+}
+
 /// Serializer adaptor for `quick_xml::Writer`
+///
+/// Namespaces of elements are declared as default namespace where needed; namespaces of
+/// attributes and qualified names in values are bound to generated prefixes (`ns0`, `ns1`, ...)
+/// unless a prefix is already declared for them.
+///
+/// Characters that can't appear in the XML version of the document (see
+/// [`Serializer::set_declaration`]) are rejected; XML 1.1 "restricted" characters are written as
+/// character references in text and attribute values.
+///
+/// Output is compact by default; see [`Serializer::set_format`] for pretty-printing.
 pub struct Serializer<'w, W: io::Write> {
 	writer: &'w mut quick_xml::Writer<W>,
+	namespaces: Namespaces,
+	declaration: Option<Declaration>,
+	version: Version,
+	format: Format,
+	escape: Escape,
+	// events of the current top-level element, recorded when indenting
+	tape: Option<Vec<Event<'static>>>,
+	// open elements
+	depth: usize,
+	wrote_any: bool,
 }
 
 impl<'w, W: io::Write> Serializer<'w, W> {
 	/// New adaptor using the writer
 	pub fn new(writer: &'w mut quick_xml::Writer<W>) -> Self {
-		Self { writer }
+		Self {
+			writer,
+			namespaces: Namespaces::default(),
+			declaration: Some(Declaration::default()),
+			version: Version::V1_1,
+			format: Format::default(),
+			escape: Escape::default(),
+			tape: None,
+			depth: 0,
+			wrote_any: false,
+		}
+	}
+
+	/// Set formatting options (indentation, line breaks, ...)
+	///
+	/// With indentation each element is kept in memory until it is complete, as indentation
+	/// depends on whether it has text content.
+	pub fn set_format(&mut self, format: Format) {
+		self.format = format;
+	}
+
+	/// Set escaping policy for text and attribute values
+	pub fn set_escape(&mut self, escape: Escape) {
+		self.escape = escape;
+	}
+
+	/// Set declaration written by [`Serializer::serialize_document`] (`None` to write none)
+	///
+	/// The default is [`Declaration::default`] (XML 1.1).  The declared version decides which
+	/// characters can be written, also for [`Serializer::serialize_element`]; without declaration
+	/// XML 1.0 is used.  Fails if the version isn't supported.
+	pub fn set_declaration(&mut self, declaration: Option<Declaration>) -> Result<()> {
+		self.version = version(declaration.as_ref())?;
+		self.declaration = declaration;
+		Ok(())
 	}
 
 	/// Serialize full document from root element
 	pub fn serialize_document<E: Element>(&mut self, element: &E) -> Result<()> {
-		self.writer
-			.write_event(Event::Decl(BytesDecl::new(b"1.1", Some(b"utf-8"), None)))?;
+		if let Some(declaration) = &self.declaration {
+			let event = declaration_event(declaration)?.into_owned();
+			self.write_top(event)?;
+		}
 		self.serialize_element(element)
 	}
 
+	/// Serialize full document: the root element and everything around it
+	///
+	/// Uses the declaration (and its version) of the document instead of
+	/// [`Serializer::set_declaration`].
+	pub fn serialize_full_document<E: Element>(&mut self, document: &Document<E>) -> Result<()> {
+		let version = version(document.declaration.as_ref())?;
+		let outer = std::mem::replace(&mut self.version, version);
+		let result = self.serialize_full_document_inner(document);
+		self.version = outer;
+		result
+	}
+
+	fn serialize_full_document_inner<E: Element>(&mut self, document: &Document<E>) -> Result<()> {
+		if let Some(declaration) = &document.declaration {
+			self.write_top(declaration_event(declaration)?)?;
+		}
+		self.serialize_misc(&document.prolog)?;
+		if let Some(doctype) = &document.doctype {
+			self.version.check_chars(doctype)?;
+			self.write_top(Event::DocType(BytesText::from_escaped_str(doctype)))?;
+		}
+		self.serialize_misc(&document.after_doctype)?;
+		self.serialize_element(&document.root)?;
+		self.serialize_misc(&document.epilog)
+	}
+
+	fn serialize_misc(&mut self, list: &[Misc]) -> Result<()> {
+		for misc in list {
+			let event = match misc {
+				Misc::Comment(text) => {
+					serializer::check_comment(text)?;
+					self.version.check_chars(text)?;
+					Event::Comment(BytesText::from_escaped_str(text))
+				},
+				Misc::ProcessingInstruction { target, data } => {
+					serializer::check_pi(target, data)?;
+					self.version.check_chars(target)?;
+					self.version.check_chars(data)?;
+					Event::PI(BytesText::from_escaped_str(pi_content(target, data)))
+				},
+			};
+			self.write_top(event)?;
+		}
+		Ok(())
+	}
+
+	/// Write event (or record it for indentation)
+	fn write(&mut self, event: Event<'_>) -> Result<()> {
+		match &mut self.tape {
+			Some(tape) => tape.push(event.into_owned()),
+			None => {
+				self.writer.write_event(event)?;
+				self.wrote_any = true;
+			},
+		}
+		Ok(())
+	}
+
+	/// Write node outside the root element (on its own line when indenting)
+	fn write_top(&mut self, event: Event<'_>) -> Result<()> {
+		if self.format.indent.is_some() && self.wrote_any {
+			let newline = self.format.newline.as_str();
+			self.writer
+				.write_event(Event::Text(BytesText::from_escaped_str(newline)))?;
+		}
+		self.write(event)
+	}
+
 	/// Serialize single element
 	pub fn serialize_element<E: Element>(&mut self, element: &E) -> Result<()> {
-		let tag = cow_bytes(element.tag());
-		let mut ser = SRef {
+		self.serialize_element_formatted(element, None)
+	}
+
+	/// Start streaming document: write declaration and start tag of the root element
+	///
+	/// `root` serializes the attributes (and possibly leading content) of the root element; the
+	/// returned writer serializes the children one at a time.
+	pub fn start_document<E: Element>(mut self, root: &E) -> Result<DocumentWriter<'w, W>> {
+		if let Some(declaration) = &self.declaration {
+			let event = declaration_event(declaration)?.into_owned();
+			self.write_top(event)?;
+		}
+		if self.format.indent.is_some() {
+			self.tape = Some(Vec::new());
+		}
+		let (qname, mut ser) = self.open_element(root, None)?;
+		let result = root.serialize(&mut ser).and_then(|()| ser.start());
+		let end = ser.end.take();
+		let siblings = std::mem::take(&mut ser.siblings);
+		let mut children = false;
+		if let Some(tape) = self.tape.take() {
+			let (mixed, has_children) = format::open_content(&tape);
+			children = has_children;
+			self.write_layout(tape, self.depth - 1)?;
+			if mixed {
+				// no whitespace between children of a root element with text
+				self.format.indent = None;
+			}
+		}
+		result.map_err(|e| e.in_element(&qname, None))?;
+		Ok(DocumentWriter {
+			serializer: self,
+			qname,
+			end,
+			siblings,
+			children,
+		})
+	}
+
+	/// Serialize element, indenting it if not already recorded for indentation
+	fn serialize_element_formatted<E: Element>(
+		&mut self,
+		element: &E,
+		index: Option<usize>,
+	) -> Result<()> {
+		if self.format.indent.is_none() || self.tape.is_some() {
+			return self.serialize_element_at(element, index);
+		}
+		self.tape = Some(Vec::new());
+		let result = self.serialize_element_at(element, index);
+		let tape = self.tape.take().expect("tape still recording");
+		self.write_layout(tape, self.depth)?;
+		result
+	}
+
+	/// Write recorded events starting at nesting level `depth` with indentation
+	fn write_layout(&mut self, tape: Vec<Event<'static>>, depth: usize) -> Result<()> {
+		let at_start = !self.wrote_any;
+		for event in format::layout(&self.format, self.escape.quote, tape, depth, at_start) {
+			self.write(event)?;
+		}
+		Ok(())
+	}
+
+	// `index`: position among siblings with the same tag, for error paths
+	fn serialize_element_at<E: Element>(
+		&mut self,
+		element: &E,
+		index: Option<usize>,
+	) -> Result<()> {
+		let (qname, mut ser) = self.open_element(element, index)?;
+		let result = element.serialize(&mut ser).and_then(|()| ser.close());
+		self.close_element();
+		result.map_err(|e| e.in_element(&qname, index))
+	}
+
+	/// Enter scope of element and prepare its start tag (declaring its namespace if needed)
+	///
+	/// Returns qualified name and the serializer for attributes and content; call
+	/// `close_element` after closing it.
+	fn open_element<E: Element>(
+		&mut self,
+		element: &E,
+		index: Option<usize>,
+	) -> Result<(String, SRef<'_, 'w, W>)> {
+		let tag = element.tag();
+		namespace::check_ncname(&tag)?;
+		let namespace = element.namespace();
+		self.namespaces.push_scope();
+		// prefix and namespace to declare
+		let mut declaration = None;
+		let qname = match namespace.as_deref() {
+			Some(namespace) => match self.namespaces.prefix(namespace, true) {
+				Some("") => tag.into_owned(),
+				Some(prefix) => format!("{}:{}", prefix, tag),
+				None => match element.prefix().filter(|prefix| usable_prefix(prefix)) {
+					Some(prefix) => {
+						self.namespaces.declare(&prefix, namespace);
+						let qname = format!("{}:{}", prefix, tag);
+						declaration = Some((prefix, namespace));
+						qname
+					},
+					None => {
+						self.namespaces.declare("", namespace);
+						declaration = Some((Cow::Borrowed(""), namespace));
+						tag.into_owned()
+					},
+				},
+			},
+			None => {
+				if self.namespaces.resolve("").is_some() {
+					// undeclare default namespace
+					self.namespaces.declare("", "");
+					declaration = Some((Cow::Borrowed(""), ""));
+				}
+				tag.into_owned()
+			},
+		};
+		let mut start = BytesStart::owned_name(qname.as_bytes().to_vec());
+		if let Some((prefix, namespace)) = declaration {
+			if let Err(e) = self.push_declaration(&mut start, &prefix, namespace) {
+				self.namespaces.pop_scope();
+				return Err(e.in_element(&qname, index));
+			}
+		}
+		self.depth += 1;
+		let ser = SRef {
+			end: Some(BytesEnd::owned(qname.as_bytes().to_vec())),
+			start: Some(start),
 			serializer: self,
-			end: Some(BytesEnd::owned(tag.to_vec())),
-			start: Some(BytesStart::owned_name(tag)),
+			siblings: HashMap::new(),
 		};
-		element.serialize(&mut ser)?;
-		ser.close()?;
+		Ok((qname, ser))
+	}
+
+	/// Leave scope of element entered with `open_element`
+	fn close_element(&mut self) {
+		self.depth -= 1;
+		self.namespaces.pop_scope();
+	}
+
+	/// Add attribute to start tag, escaping the value
+	fn push_attribute(
+		&self,
+		start: &mut BytesStart<'static>,
+		key: &str,
+		value: &str,
+	) -> Result<()> {
+		let value = escape(self.version, &self.escape, value, true)?;
+		match self.escape.quote {
+			Quote::Double => start.push_attribute(Attribute {
+				key: key.as_bytes(),
+				value: cow_bytes(value),
+			}),
+			Quote::Single => {
+				let name_len = start.name().len();
+				let mut content = start.to_vec();
+				content.extend_from_slice(format!(" {}='{}'", key, value).as_bytes());
+				*start = BytesStart::owned(content, name_len);
+			},
+		}
 		Ok(())
 	}
+
+	/// Add namespace declaration (for the default namespace if `prefix` is empty)
+	fn push_declaration(
+		&self,
+		start: &mut BytesStart<'static>,
+		prefix: &str,
+		namespace: &str,
+	) -> Result<()> {
+		let key = if prefix.is_empty() {
+			String::from("xmlns")
+		} else {
+			format!("xmlns:{}", prefix)
+		};
+		self.push_attribute(start, &key, namespace)
+	}
+
+	/// Prefix not bound in the current scope
+	fn generate_prefix(&self) -> String {
+		(0..)
+			.map(|n| format!("ns{}", n))
+			.find(|prefix| self.namespaces.resolve(prefix).is_none())
+			.expect("infinite candidates")
+	}
+}
+
+/// Streaming writer for the children of a root element (see [`Serializer::start_document`])
+///
+/// Only the child currently written is kept in memory (even with indentation).
+/// [`DocumentWriter::finish`] closes the root element; dropping the writer leaves the document
+/// incomplete.
+pub struct DocumentWriter<'w, W: io::Write> {
+	serializer: Serializer<'w, W>,
+	qname: String,
+	end: Option<BytesEnd<'static>>,
+	siblings: HashMap<String, usize>,
+	// whether to put the end tag on its own line when indenting
+	children: bool,
+}
+
+impl<'w, W: io::Write> DocumentWriter<'w, W> {
+	/// Serialize child element of the root element
+	pub fn serialize_element<E: Element>(&mut self, element: &E) -> Result<()> {
+		self.serialize_inner(element)
+	}
+
+	/// Serialize children of the root element (e.g. [`InnerIter`][crate::serializer::InnerIter])
+	pub fn serialize_inner<I: Inner>(&mut self, inner: &I) -> Result<()> {
+		self.children = true;
+		self.with_root(|mut ser| inner.serialize_elements(&mut ser))
+	}
+
+	/// Underlying writer (e.g. to take output buffered in memory)
+	pub fn inner(&mut self) -> &mut W {
+		self.serializer.writer.inner()
+	}
+
+	/// Flush the underlying writer
+	pub fn flush(&mut self) -> Result<()> {
+		self.serializer.writer.inner().flush()?;
+		Ok(())
+	}
+
+	/// Close the root element and flush the underlying writer
+	pub fn finish(mut self) -> Result<()> {
+		if self.children && self.serializer.format.indent.is_some() {
+			let newline = self.serializer.format.newline.as_str();
+			self.serializer
+				.write(Event::Text(BytesText::from_escaped_str(newline)))?;
+		}
+		self.with_root(|ser| ser.close())?;
+		self.serializer.close_element();
+		self.flush()
+	}
+
+	fn with_root<T>(&mut self, f: impl FnOnce(&mut SRef<'_, 'w, W>) -> Result<T>) -> Result<T> {
+		let mut ser = SRef {
+			serializer: &mut self.serializer,
+			start: None,
+			end: self.end.take(),
+			siblings: std::mem::take(&mut self.siblings),
+		};
+		let result = f(&mut ser);
+		self.end = ser.end.take();
+		self.siblings = std::mem::take(&mut ser.siblings);
+		result.map_err(|e| e.in_element(&self.qname, None))
+	}
 }
 
 struct SRef<'a, 'w, W: io::Write> {
 	serializer: &'a mut Serializer<'w, W>,
 	start: Option<BytesStart<'static>>,
 	end: Option<BytesEnd<'static>>,
+	// count children per (expanded) name for error paths
+	siblings: HashMap<String, usize>,
 }
 
 impl<'a, 'w, W: io::Write> SRef<'a, 'w, W> {
 	fn start(&mut self) -> Result<()> {
 		if let Some(s) = self.start.take() {
-			self.serializer.writer.write_event(Event::Start(s))?;
+			self.serializer.write(Event::Start(s))?;
 		} else {
 			assert!(self.end.is_some(), "element already closed");
 		}
@@ -84,43 +574,187 @@ impl<'a, 'w, W: io::Write> SRef<'a, 'w, W> {
 	}
 
 	fn close(&mut self) -> Result<()> {
-		if let Some(s) = self.start.take() {
-			self.serializer.writer.write_event(Event::Empty(s))?;
-			self.end = None;
-		} else if let Some(e) = self.end.take() {
-			self.serializer.writer.write_event(Event::End(e))?;
+		if self.serializer.format.self_close {
+			if let Some(s) = self.start.take() {
+				self.serializer.write(Event::Empty(s))?;
+				self.end = None;
+			}
+		} else {
+			self.start()?;
+		}
+		if let Some(e) = self.end.take() {
+			self.serializer.write(Event::End(e))?;
 		}
 		Ok(())
 	}
 }
 
 impl<'a, 'w, W: io::Write> serializer::Serializer for &'_ mut SRef<'a, 'w, W> {
-	fn serialize_attribute(&mut self, key: &str, value: Cow<'_, str>) -> Result<()> {
+	fn serialize_attribute(&mut self, name: Name<'_>, value: Cow<'_, str>) -> Result<()> {
 		let start = self.start.as_mut().expect("element already started");
-		let key = key.as_bytes();
-		let value = cow_bytes(value);
-		start.push_attribute(Attribute { key, value });
+		namespace::check_ncname(name.local).map_err(|e| e.in_attribute(name.local))?;
+		if name
+			.namespace
+			.map_or(name.local == "xmlns", |ns| ns == namespace::XMLNS)
+		{
+			// use `declare_namespace` instead
+			return Err(errors::invalid_name(name.local).in_attribute(name.local));
+		}
+		let key = match name.namespace {
+			None => Cow::Borrowed(name.local),
+			Some(namespace) => {
+				let namespaces = &mut self.serializer.namespaces;
+				let prefix = match namespaces.prefix(namespace, false) {
+					Some(prefix) => prefix.to_owned(),
+					None => {
+						let prefix = self.serializer.generate_prefix();
+						self.serializer.namespaces.declare(&prefix, namespace);
+						self.serializer
+							.push_declaration(start, &prefix, namespace)?;
+						prefix
+					},
+				};
+				Cow::Owned(format!("{}:{}", prefix, name.local))
+			},
+		};
+		if start
+			.attributes()
+			.with_checks(false)
+			.flatten()
+			.any(|a| a.key == key.as_bytes())
+		{
+			return Err(errors::duplicate_attribute(&key).in_attribute(&key));
+		}
+		self.serializer
+			.push_attribute(start, &key, &value)
+			.map_err(|e| e.in_attribute(&key))
+	}
+
+	fn declare_namespace(&mut self, prefix: &str, namespace: &str) -> Result<()> {
+		// only possible before the start tag was written
+		let start = match self.start.as_mut() {
+			Some(start) => start,
+			None => return Ok(()),
+		};
+		let namespaces = &mut self.serializer.namespaces;
+		if usable_prefix(prefix)
+			&& namespaces.prefix(namespace, false).is_none()
+			&& !namespaces.declared_in_scope(prefix)
+		{
+			namespaces.declare(prefix, namespace);
+			self.serializer.push_declaration(start, prefix, namespace)?;
+		}
 		Ok(())
 	}
 
+	fn qualified_name(&mut self, name: Name<'_>) -> Result<String> {
+		namespace::check_ncname(name.local)?;
+		let namespaces = &mut self.serializer.namespaces;
+		let namespace = match name.namespace {
+			None if namespaces.resolve("").is_some() => {
+				return Err(errors::custom(format!(
+					"can't refer to {} without namespace in scope of a default namespace",
+					name
+				)));
+			},
+			None => return Ok(name.local.into()),
+			Some(namespace) => namespace,
+		};
+		if let Some(prefix) = namespaces.prefix(namespace, true) {
+			return Ok(if prefix.is_empty() {
+				name.local.into()
+			} else {
+				format!("{}:{}", prefix, name.local)
+			});
+		}
+		// only possible before the start tag was written
+		let start = self.start.as_mut().ok_or_else(|| {
+			errors::custom(format!(
+				"namespace {:?} not declared for {}",
+				namespace, name
+			))
+		})?;
+		let prefix = self.serializer.generate_prefix();
+		self.serializer.namespaces.declare(&prefix, namespace);
+		self.serializer
+			.push_declaration(start, &prefix, namespace)?;
+		Ok(format!("{}:{}", prefix, name.local))
+	}
+
 	fn serialize_text(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.start()?;
+		let text = escape(
+			self.serializer.version,
+			&self.serializer.escape,
+			&text,
+			false,
+		)?;
+		self.serializer
+			.write(Event::Text(BytesText::from_escaped_str(text)))?;
+		Ok(())
+	}
+
+	fn serialize_cdata(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.serializer.version.check_chars(&text)?;
+		self.start()?;
+		// end the section before `>` of `]]>` and continue in a new one
+		let text = text.replace("]]>", "]]]]><![CDATA[>");
+		self.serializer
+			.write(Event::CData(BytesCData::from_str(&text)))?;
+		Ok(())
+	}
+
+	fn serialize_comment(&mut self, text: Cow<'_, str>) -> Result<()> {
+		serializer::check_comment(&text)?;
+		self.serializer.version.check_chars(&text)?;
+		self.start()?;
+		self.serializer
+			.write(Event::Comment(BytesText::from_escaped_str(text)))?;
+		Ok(())
+	}
+
+	fn serialize_pi(&mut self, target: &str, data: &str) -> Result<()> {
+		serializer::check_pi(target, data)?;
+		self.serializer.version.check_chars(target)?;
+		self.serializer.version.check_chars(data)?;
 		self.start()?;
 		self.serializer
-			.writer
-			.write_event(Event::Text(BytesText::from_plain_str(&text)))?;
+			.write(Event::PI(BytesText::from_escaped_str(pi_content(
+				target, data,
+			))))?;
 		Ok(())
 	}
 
 	fn serialize_element<E: Element>(&mut self, element: &E) -> Result<()> {
 		self.start()?;
-		self.serializer.serialize_element(element)
+		let tag = element.tag();
+		let namespace = element.namespace();
+		let name = Name::new(namespace.as_deref(), &tag).to_string();
+		let index = match self.siblings.get_mut(&name) {
+			Some(count) => {
+				*count += 1;
+				*count
+			},
+			None => {
+				self.siblings.insert(name, 1);
+				1
+			},
+		};
+		self.serializer
+			.serialize_element_formatted(element, Some(index))
 	}
 }
 
 #[cfg(test)]
 mod test {
 	use super::*;
-	use crate::test_struct::*;
+	use crate::{
+		quick_xml::{
+			Indent,
+			Newline,
+		},
+		test_struct::*,
+	};
 
 	#[test]
 	fn test() {
@@ -129,4 +763,606 @@ mod test {
 			Data::TEST_SERIALIZE_DOCUMENT_1,
 		);
 	}
+
+	struct Broken;
+
+	impl serializer::Value<Broken> for Broken {
+		fn serialize_value(_data: &Broken) -> Result<Cow<'_, str>> {
+			Err(crate::errors::custom("broken"))
+		}
+	}
+
+	impl serializer::FixedElement for Broken {
+		const TAG: &'static str = "broken";
+
+		fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+			let value = <Broken as serializer::Value<Broken>>::serialize_value(self)
+				.map_err(|e| e.in_attribute("value"))?;
+			serializer.serialize_attribute("value".into(), value)
+		}
+	}
+
+	struct Outer;
+
+	impl serializer::FixedElement for Outer {
+		const TAG: &'static str = "outer";
+
+		fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+			serializer.serialize_element(&Item { value: 1 })?;
+			serializer.serialize_element(&Broken)?;
+			serializer.serialize_element(&Broken)
+		}
+	}
+
+	#[test]
+	fn test_error_path() {
+		let e = serialize_document(&Outer).unwrap_err();
+		assert_eq!(e.path().to_string(), "/outer/broken[1]/@value");
+		assert_eq!(e.to_string(), "/outer/broken[1]/@value: broken");
+	}
+
+	struct Namespaced;
+
+	impl serializer::FixedElement for Namespaced {
+		const NAMESPACE: Option<&'static str> = Some("urn:a");
+		const TAG: &'static str = "root";
+
+		fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+			serializer.serialize_attribute(Name::new(Some("urn:b"), "k"), "1".into())?;
+			serializer
+				.serialize_attribute(Name::new(Some(crate::namespace::XML), "lang"), "en".into())?;
+			serializer.serialize_element(&Child)?;
+			serializer.serialize_element(&serializer::EmptyElement("plain".into()))?;
+			serializer.serialize_element(&Other)
+		}
+	}
+
+	struct Child;
+
+	impl serializer::FixedElement for Child {
+		const NAMESPACE: Option<&'static str> = Some("urn:a");
+		const TAG: &'static str = "child";
+
+		fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+			serializer.serialize_attribute(Name::new(Some("urn:b"), "k"), "3".into())
+		}
+	}
+
+	struct Other;
+
+	impl serializer::FixedElement for Other {
+		const NAMESPACE: Option<&'static str> = Some("urn:b");
+		const TAG: &'static str = "other";
+
+		fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+			serializer.serialize_attribute(Name::new(Some("urn:c"), "k"), "2".into())
+		}
+	}
+
+	#[test]
+	fn test_namespaces() {
+		struct Outer;
+
+		impl serializer::FixedElement for Outer {
+			const TAG: &'static str = "outer";
+
+			fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+				serializer.serialize_element(&Other)?;
+				serializer.serialize_element(&Namespaced)
+			}
+		}
+
+		let mut buf = Vec::new();
+		let mut writer = quick_xml::Writer::new(&mut buf);
+		Serializer::new(&mut writer)
+			.serialize_element(&Outer)
+			.unwrap();
+		assert_eq!(
+			String::from_utf8(buf).unwrap(),
+			concat!(
+				r#"<outer><other xmlns="urn:b" xmlns:ns0="urn:c" ns0:k="2"/>"#,
+				r#"<root xmlns="urn:a" xmlns:ns0="urn:b" ns0:k="1" xml:lang="en">"#,
+				r#"<child ns0:k="3"/><plain xmlns=""/>"#,
+				r#"<ns0:other xmlns:ns1="urn:c" ns1:k="2"/></root></outer>"#,
+			)
+		);
+	}
+
+	#[test]
+	fn test_derived_namespaces() {
+		assert_eq!(
+			serialize_document(&Feed::test_result_1()).unwrap(),
+			Feed::TEST_SERIALIZE_DOCUMENT_1,
+		);
+	}
+
+	#[test]
+	fn test_comment_and_cdata() {
+		assert_eq!(
+			serialize_document(&Script::test_result_1()).unwrap(),
+			Script::TEST_SERIALIZE_DOCUMENT_1,
+		);
+
+		struct Stylesheet;
+
+		impl serializer::FixedElement for Stylesheet {
+			const TAG: &'static str = "doc";
+
+			fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+				serializer.serialize_pi("page-break", "")?;
+				serializer.serialize_pi("php", "echo 1;")?;
+				serializer.serialize_comment("bad--comment".into())
+			}
+		}
+
+		let e = serialize_document(&Stylesheet).unwrap_err();
+		assert_eq!(e.path().to_string(), "/doc");
+		let mut buf = Vec::new();
+		let mut writer = quick_xml::Writer::new(&mut buf);
+		let _ = Serializer::new(&mut writer).serialize_element(&Stylesheet);
+		assert_eq!(
+			String::from_utf8(buf).unwrap(),
+			"<doc><?page-break?><?php echo 1;?>"
+		);
+	}
+
+	#[test]
+	fn test_qname_values() {
+		assert_eq!(
+			serialize_document(&Typed::test_result_1()).unwrap(),
+			Typed::TEST_SERIALIZE_DOCUMENT_1,
+		);
+
+		struct Late;
+
+		impl serializer::FixedElement for Late {
+			const NAMESPACE: Option<&'static str> = Some("urn:a");
+			const TAG: &'static str = "late";
+
+			fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+				assert_eq!(
+					serializer.qualified_name(Name::new(Some("urn:a"), "x"))?,
+					"x"
+				);
+				assert!(serializer.qualified_name(Name::from("x")).is_err());
+				serializer.serialize_text("text".into())?;
+				serializer
+					.qualified_name(Name::new(Some("urn:b"), "x"))
+					.map(drop)
+			}
+		}
+
+		let e = serialize_document(&Late).unwrap_err();
+		assert_eq!(
+			e.to_string(),
+			r#"/late: namespace "urn:b" not declared for {urn:b}x"#
+		);
+	}
+
+	#[test]
+	fn test_flags() {
+		assert_eq!(
+			serialize_document(&Flags::TEST_RESULT_1).unwrap(),
+			Flags::TEST_SERIALIZE_DOCUMENT_1,
+		);
+	}
+
+	#[test]
+	fn test_full_document() {
+		assert_eq!(
+			serialize_full_document(&test_full_document_1()).unwrap(),
+			TEST_SERIALIZE_FULL_DOCUMENT_1,
+		);
+		// without declaration
+		let document = Document {
+			declaration: None,
+			..Document::new(Item { value: 2 })
+		};
+		assert_eq!(
+			serialize_full_document(&document).unwrap(),
+			r#"<item value="2"/>"#
+		);
+	}
+
+	/// Writes value as attribute and text
+	struct Control(&'static str);
+
+	impl serializer::FixedElement for Control {
+		const TAG: &'static str = "c";
+
+		fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+			serializer.serialize_attribute("v".into(), self.0.into())?;
+			serializer.serialize_text(self.0.into())
+		}
+	}
+
+	fn serialize_with(declaration: Option<Declaration>, element: &Control) -> Result<String> {
+		let mut buf = Vec::new();
+		let mut writer = quick_xml::Writer::new(&mut buf);
+		let mut serializer = Serializer::new(&mut writer);
+		serializer.set_declaration(declaration)?;
+		serializer.serialize_document(element)?;
+		Ok(String::from_utf8(buf).unwrap())
+	}
+
+	#[test]
+	fn test_declaration() {
+		assert_eq!(
+			serialize_with(Some(Declaration::new(Version::V1_0)), &Control("a<b")).unwrap(),
+			r#"<?xml version="1.0" encoding="utf-8"?><c v="a&lt;b">a&lt;b</c>"#,
+		);
+		let declaration = Declaration {
+			version: "1.0".into(),
+			encoding: None,
+			standalone: Some(false),
+		};
+		assert_eq!(
+			serialize_with(Some(declaration), &Control("x")).unwrap(),
+			r#"<?xml version="1.0" standalone="no"?><c v="x">x</c>"#,
+		);
+		assert_eq!(
+			serialize_with(None, &Control("x")).unwrap(),
+			r#"<c v="x">x</c>"#,
+		);
+		let declaration = Declaration {
+			version: "2.0".into(),
+			..Declaration::default()
+		};
+		assert!(serialize_with(Some(declaration), &Control("x")).is_err());
+		let declaration = Declaration {
+			encoding: Some("utf 8".into()),
+			..Declaration::default()
+		};
+		assert!(serialize_with(Some(declaration), &Control("x")).is_err());
+	}
+
+	#[test]
+	fn test_version_chars() {
+		// XML 1.0 doesn't allow C0 controls
+		let e =
+			serialize_with(Some(Declaration::new(Version::V1_0)), &Control("a\u{1}")).unwrap_err();
+		assert!(matches!(
+			e.kind(),
+			crate::ErrorKind::InvalidCharacter { character: '\u{1}' }
+		));
+		assert_eq!(e.to_string(), "/c/@v: Invalid character U+0001");
+		assert!(serialize_with(None, &Control("\u{FFFE}")).is_err());
+		// XML 1.1 needs character references for them
+		assert_eq!(
+			serialize_with(Some(Declaration::new(Version::V1_1)), &Control("a\u{1}\u{86}\tb")).unwrap(),
+			"<?xml version=\"1.1\" encoding=\"utf-8\"?><c v=\"a&#x1;&#x86;\tb\">a&#x1;&#x86;\tb</c>",
+		);
+		let e =
+			serialize_with(Some(Declaration::new(Version::V1_1)), &Control("\u{0}")).unwrap_err();
+		assert!(matches!(
+			e.kind(),
+			crate::ErrorKind::InvalidCharacter { character: '\u{0}' }
+		));
+
+		// the declaration of the document decides
+		let document = Document {
+			declaration: None,
+			..Document::new(Control("\u{1}"))
+		};
+		assert!(serialize_full_document(&document).is_err());
+	}
+
+	enum Node {
+		Element(Tree),
+		Text(&'static str),
+		Comment(&'static str),
+	}
+
+	struct Tree(&'static str, Vec<(Name<'static>, &'static str)>, Vec<Node>);
+
+	fn tree(tag: &'static str, content: Vec<Node>) -> Node {
+		Node::Element(Tree(tag, Vec::new(), content))
+	}
+
+	impl Element for Tree {
+		fn tag(&self) -> Cow<'_, str> {
+			self.0.into()
+		}
+
+		fn serialize<S: serializer::Serializer>(&self, mut serializer: S) -> Result<()> {
+			for (name, value) in &self.1 {
+				serializer.serialize_attribute(*name, (*value).into())?;
+			}
+			for node in &self.2 {
+				match node {
+					Node::Element(element) => serializer.serialize_element(element)?,
+					Node::Text(text) => serializer.serialize_text((*text).into())?,
+					Node::Comment(text) => serializer.serialize_comment((*text).into())?,
+				}
+			}
+			Ok(())
+		}
+	}
+
+	fn serialize_formatted(format: Format, tree: &Tree) -> Result<String> {
+		let mut buf = Vec::new();
+		let mut writer = quick_xml::Writer::new(&mut buf);
+		let mut serializer = Serializer::new(&mut writer);
+		serializer.set_format(format);
+		serializer.serialize_document(tree)?;
+		Ok(String::from_utf8(buf).unwrap())
+	}
+
+	#[test]
+	fn test_format() {
+		let preserve = Name::new(Some(crate::namespace::XML), "space");
+		let root = Tree(
+			"root",
+			Vec::new(),
+			vec![
+				tree("a", Vec::new()),
+				Node::Comment(" c "),
+				tree(
+					"p",
+					vec![
+						Node::Text("Hello "),
+						tree("b", vec![tree("i", Vec::new())]),
+						Node::Text("!"),
+					],
+				),
+				Node::Element(Tree(
+					"pre",
+					vec![(preserve, "preserve")],
+					vec![tree("x", Vec::new())],
+				)),
+				tree(
+					"list",
+					vec![
+						tree("item", vec![Node::Text("1")]),
+						tree("item", Vec::new()),
+					],
+				),
+			],
+		);
+		assert_eq!(
+			serialize_formatted(Format::pretty(), &root).unwrap(),
+			concat!(
+				"<?xml version=\"1.1\" encoding=\"utf-8\"?>\n",
+				"<root>\n",
+				"  <a/>\n",
+				"  <!-- c -->\n",
+				"  <p>Hello <b><i/></b>!</p>\n",
+				"  <pre xml:space=\"preserve\"><x/></pre>\n",
+				"  <list>\n",
+				"    <item>1</item>\n",
+				"    <item/>\n",
+				"  </list>\n",
+				"</root>",
+			)
+		);
+
+		let root = Tree(
+			"root",
+			Vec::new(),
+			vec![tree("a", vec![tree("b", Vec::new())])],
+		);
+		let format = Format {
+			self_close: false,
+			..Format::default()
+		};
+		assert_eq!(
+			serialize_formatted(format, &root).unwrap(),
+			r#"<?xml version="1.1" encoding="utf-8"?><root><a><b></b></a></root>"#,
+		);
+		let format = Format {
+			indent: Some(Indent::Tabs(1)),
+			newline: Newline::CrLf,
+			self_close: false,
+			wrap_attributes: None,
+		};
+		assert_eq!(
+			serialize_formatted(format, &root).unwrap(),
+			concat!(
+				"<?xml version=\"1.1\" encoding=\"utf-8\"?>\r\n",
+				"<root>\r\n\t<a>\r\n\t\t<b></b>\r\n\t</a>\r\n</root>",
+			)
+		);
+	}
+
+	#[test]
+	fn test_format_wrap_attributes() {
+		let attributes = vec![
+			(Name::new(None, "first"), "value of the first attribute"),
+			(Name::new(None, "second"), "2"),
+		];
+		let root = Tree(
+			"root",
+			Vec::new(),
+			vec![
+				Node::Element(Tree("long", attributes.clone(), Vec::new())),
+				Node::Element(Tree("short", attributes[1..].to_vec(), Vec::new())),
+			],
+		);
+		let format = Format {
+			wrap_attributes: Some(40),
+			..Format::pretty()
+		};
+		let mut buf = Vec::new();
+		let mut writer = quick_xml::Writer::new(&mut buf);
+		let mut serializer = Serializer::new(&mut writer);
+		serializer.set_format(format);
+		serializer.serialize_element(&root).unwrap();
+		assert_eq!(
+			String::from_utf8(buf).unwrap(),
+			concat!(
+				"<root>\n",
+				"  <long\n",
+				"    first=\"value of the first attribute\"\n",
+				"    second=\"2\"/>\n",
+				"  <short second=\"2\"/>\n",
+				"</root>",
+			)
+		);
+	}
+
+	#[test]
+	fn test_invalid_names() {
+		let error = |root: Tree| {
+			let e = serialize_formatted(Format::default(), &root).unwrap_err();
+			(e.path().to_string(), e.into_kind().to_string())
+		};
+		let attribute = |local| vec![(Name::new(None, local), "1")];
+		assert_eq!(
+			error(Tree("root", Vec::new(), vec![tree("a b", Vec::new())])),
+			(String::from("/root"), String::from("Invalid name \"a b\"")),
+		);
+		assert!(
+			serialize_formatted(Format::default(), &Tree("x:y", Vec::new(), Vec::new())).is_err()
+		);
+		assert_eq!(
+			error(Tree("root", attribute("a b"), Vec::new())),
+			(
+				String::from("/root/@a b"),
+				String::from("Invalid name \"a b\"")
+			),
+		);
+		assert_eq!(
+			error(Tree("root", attribute("xmlns"), Vec::new())),
+			(
+				String::from("/root/@xmlns"),
+				String::from("Invalid name \"xmlns\"")
+			),
+		);
+		let lang = Name::new(Some(crate::namespace::XML), "lang");
+		assert_eq!(
+			error(Tree("root", vec![(lang, "en"), (lang, "de")], Vec::new())),
+			(
+				String::from("/root/@xml:lang"),
+				String::from("Duplicate attribute \"xml:lang\"")
+			),
+		);
+		assert_eq!(
+			error(Tree("root", Vec::new(), vec![Node::Text("a\u{0}")])),
+			(
+				String::from("/root"),
+				String::from("Invalid character U+0000")
+			),
+		);
+	}
+
+	#[test]
+	fn test_escape() {
+		let serialize_text = |escape: Escape, text: &'static str| {
+			let mut buf = Vec::new();
+			let mut writer = quick_xml::Writer::new(&mut buf);
+			let mut serializer = Serializer::new(&mut writer);
+			serializer.set_escape(escape);
+			serializer.serialize_element(&Control(text)).unwrap();
+			String::from_utf8(buf).unwrap()
+		};
+		let serialize = |escape: Escape| serialize_text(escape, "<a\t\"b'>\n\u{E9}&");
+		assert_eq!(
+			serialize(Escape::default()),
+			"<c v=\"&lt;a\t&quot;b&apos;&gt;\n\u{E9}&amp;\">&lt;a\t&quot;b&apos;&gt;\n\u{E9}&amp;</c>",
+		);
+		let escape = Escape {
+			greater_than: false,
+			quotes: false,
+			attribute_whitespace: true,
+			quote: Quote::Single,
+			ascii_only: true,
+		};
+		assert_eq!(
+			serialize(escape.clone()),
+			"<c v='&lt;a&#9;\"b&apos;>&#10;&#xE9;&amp;'>&lt;a\t\"b'>\n&#xE9;&amp;</c>",
+		);
+		// `]]>` isn't allowed in text, but in attribute values
+		assert_eq!(serialize_text(escape, "a]]>b"), "<c v='a]]>b'>a]]&gt;b</c>",);
+		let escape = Escape {
+			quotes: false,
+			..Escape::default()
+		};
+		assert_eq!(
+			serialize(escape),
+			"<c v=\"&lt;a\t&quot;b'&gt;\n\u{E9}&amp;\">&lt;a\t\"b'&gt;\n\u{E9}&amp;</c>",
+		);
+
+		// namespace declarations and wrapped attributes use the same quote
+		let mut buf = Vec::new();
+		let mut writer = quick_xml::Writer::new(&mut buf);
+		let mut serializer = Serializer::new(&mut writer);
+		serializer.set_escape(Escape {
+			quote: Quote::Single,
+			..Escape::default()
+		});
+		serializer.set_format(Format {
+			wrap_attributes: Some(10),
+			..Format::pretty()
+		});
+		serializer.serialize_element(&Namespaced).unwrap();
+		assert_eq!(
+			String::from_utf8(buf).unwrap(),
+			concat!(
+				"<root\n  xmlns='urn:a'\n  xmlns:ns0='urn:b'\n  ns0:k='1'\n  xml:lang='en'>\n",
+				"  <child ns0:k='3'/>\n",
+				"  <plain xmlns=''/>\n",
+				"  <ns0:other\n    xmlns:ns1='urn:c'\n    ns1:k='2'/>\n",
+				"</root>",
+			)
+		);
+	}
+
+	fn stream(format: Format, root: &Tree) -> Result<String> {
+		let mut buf = Vec::new();
+		let mut writer = quick_xml::Writer::new(&mut buf);
+		let mut serializer = Serializer::new(&mut writer);
+		serializer.set_declaration(None)?;
+		serializer.set_format(format);
+		let mut document = serializer.start_document(root)?;
+		document.serialize_element(&Item { value: 1 })?;
+		document.flush()?;
+		document.serialize_inner(&serializer::InnerIter((2..4).map(|value| Item { value })))?;
+		document.serialize_inner(&serializer::InnerFn(|| vec![Item { value: 4 }]))?;
+		document.finish()?;
+		Ok(String::from_utf8(buf).unwrap())
+	}
+
+	#[test]
+	fn test_document_writer() {
+		let attribute = vec![(Name::new(None, "k"), "v")];
+		let root = Tree("rows", attribute.clone(), Vec::new());
+		assert_eq!(
+			stream(Format::default(), &root).unwrap(),
+			concat!(
+				r#"<rows k="v"><item value="1"/><item value="2"/><item value="3"/>"#,
+				r#"<item value="4"/></rows>"#,
+			)
+		);
+		let root = Tree("rows", attribute, vec![Node::Comment(" data ")]);
+		assert_eq!(
+			stream(Format::pretty(), &root).unwrap(),
+			concat!(
+				"<rows k=\"v\">\n",
+				"  <!-- data -->\n",
+				"  <item value=\"1\"/>\n",
+				"  <item value=\"2\"/>\n",
+				"  <item value=\"3\"/>\n",
+				"  <item value=\"4\"/>\n",
+				"</rows>",
+			)
+		);
+		// no whitespace in a root element with text
+		let root = Tree("rows", Vec::new(), vec![Node::Text("t")]);
+		assert_eq!(
+			stream(Format::pretty(), &root).unwrap(),
+			concat!(
+				r#"<rows>t<item value="1"/><item value="2"/><item value="3"/>"#,
+				r#"<item value="4"/></rows>"#,
+			)
+		);
+
+		let mut buf = Vec::new();
+		let mut writer = quick_xml::Writer::new(&mut buf);
+		let mut document = Serializer::new(&mut writer)
+			.start_document(&serializer::EmptyElement("rows".into()))
+			.unwrap();
+		document.serialize_element(&Broken).unwrap_err();
+		let e = document.serialize_element(&Broken).unwrap_err();
+		assert_eq!(e.path().to_string(), "/rows/broken[2]/@value");
+	}
 }
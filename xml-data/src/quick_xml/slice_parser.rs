@@ -0,0 +1,337 @@
+use crate::{
+	errors,
+	parser::{
+		Element,
+		ElementState,
+		Warning,
+	},
+	quick_xml::driver::{
+		self,
+		Attribute,
+		Node,
+		Source,
+		Start,
+	},
+	Document,
+	Error,
+	Position,
+	Result,
+};
+use quick_xml::events::{
+	attributes::Attributes,
+	BytesStart,
+	Event,
+};
+use std::borrow::Cow;
+
+/// Parser adaptor for `quick_xml::Reader` reading from memory
+///
+/// Unlike [`Parser`][`super::Parser`] this passes text and attribute values borrowed from the input
+/// to the parse states if they don't need unescaping (and decoding).
+pub struct SliceParser<'i, 'r> {
+	inner: &'r mut quick_xml::Reader<&'i [u8]>,
+	input: &'i [u8],
+	base: usize,        // reader position at the start of `input`
+	position: Position, // after the last event
+	collect_errors: bool,
+	warnings: Vec<Warning>, // from the last parsed document
+}
+
+impl<'i, 'r> SliceParser<'i, 'r> {
+	/// Create a new adaptor from a reader
+	///
+	/// Positions start counting at line 1 from the current position of the reader.
+	pub fn new(inner: &'r mut quick_xml::Reader<&'i [u8]>) -> Self {
+		let input = *inner.get_ref();
+		let base = inner.buffer_position();
+		Self {
+			inner,
+			input,
+			base,
+			position: Position {
+				offset: base,
+				..Position::START
+			},
+			collect_errors: false,
+			warnings: Vec::new(),
+		}
+	}
+
+	/// Enable or disable collecting errors
+	///
+	/// When collecting errors, recoverable errors (see [`Error::is_recoverable`]) are recorded and
+	/// parsing continues (skipping elements that failed to parse); if any error was recorded
+	/// parsing fails with [`ErrorKind::Multiple`][`crate::ErrorKind::Multiple`] containing all of
+	/// them.
+	pub fn set_collect_errors(&mut self, collect_errors: bool) {
+		self.collect_errors = collect_errors;
+	}
+
+	/// Warnings recorded while parsing the last document
+	///
+	/// States record warnings when they ignore data (see
+	/// [`ParseContext::warn`][`crate::parser::ParseContext::warn`]), e.g. for
+	/// `#[xml_data(ignore_unknown)]`.
+	pub fn warnings(&self) -> &[Warning] {
+		&self.warnings
+	}
+
+	/// Take warnings recorded while parsing the last document
+	pub fn take_warnings(&mut self) -> Vec<Warning> {
+		std::mem::take(&mut self.warnings)
+	}
+
+	/// Advance position to (reader) offset
+	fn advance_to(&mut self, offset: usize) {
+		let from = self.position.offset - self.base;
+		let to = (offset - self.base).min(self.input.len());
+		if to > from {
+			self.position.advance(&self.input[from..to]);
+		}
+	}
+
+	/// Advance position to the next non-whitespace byte (start of markup)
+	fn skip_whitespace(&mut self) {
+		let from = self.position.offset - self.base;
+		let len = self.input[from..]
+			.iter()
+			.take_while(|b| b.is_ascii_whitespace())
+			.count();
+		self.position.advance(&self.input[from..][..len]);
+	}
+
+	/// Start tag content (after the `<` at `offset` in the input) borrowed from the input
+	fn reborrow(&self, start: &BytesStart<'i>, offset: usize) -> Result<&'i [u8]> {
+		let content: &[u8] = start;
+		self.input
+			.get(offset + 1..)
+			.and_then(|rem| rem.get(..content.len()))
+			.filter(|&borrowed| borrowed == content)
+			.ok_or_else(errors::cannot_borrow)
+	}
+
+	/// Unescape and decode data from input; borrows from input if possible
+	fn decode(&self, raw: Cow<'i, [u8]>, unescape: bool) -> Result<Cow<'i, str>> {
+		match raw {
+			Cow::Borrowed(raw) if !unescape || !raw.contains(&b'&') => Ok(self.inner.decode(raw)),
+			raw => {
+				let unescaped = if unescape {
+					quick_xml::escape::unescape(&raw).map_err(quick_xml::Error::EscapeError)?
+				} else {
+					Cow::Borrowed(&*raw)
+				};
+				Ok(Cow::Owned(self.inner.decode(&unescaped).into_owned()))
+			},
+		}
+	}
+
+	fn start(&self, start: &BytesStart<'i>, offset: usize, empty: bool) -> Result<Start<'i>> {
+		let content = self.reborrow(start, offset)?;
+		let name_len = start.name().len();
+		let tag = self.inner.decode(&content[..name_len]);
+		let attributes = Attributes::new(content, name_len)
+			.map(|attr| {
+				let attr = attr.map_err(quick_xml::Error::InvalidAttr)?;
+				Ok(Attribute {
+					key: self.inner.decode(attr.key),
+					namespace: None,
+					value: self.decode(attr.value, true)?,
+				})
+			})
+			.collect::<Result<_>>()?;
+		Ok(Start {
+			tag,
+			namespace: None,
+			attributes,
+			empty,
+		})
+	}
+
+	/// Decode event starting at `offset` in the input
+	fn node(&self, event: Event<'i>, offset: usize) -> Result<Node<'i>> {
+		Ok(match event {
+			Event::Start(s) => Node::Start(self.start(&s, offset, false)?),
+			Event::Empty(s) => Node::Start(self.start(&s, offset, true)?),
+			Event::End(_) => Node::End,
+			Event::Text(t) => Node::Text(self.decode(t.into_inner(), true)?),
+			Event::CData(t) => Node::Text(self.decode(t.into_inner(), false)?),
+			Event::Comment(t) => Node::Comment(self.decode(t.into_inner(), false)?),
+			Event::PI(t) => Node::PI(self.decode(t.into_inner(), false)?),
+			Event::Decl(d) => Node::Decl(driver::declaration(self.inner, &d)?),
+			Event::DocType(t) => Node::DocType(self.decode(t.into_inner(), false)?),
+			Event::Eof => Node::Eof,
+		})
+	}
+
+	/// Parse a single (root) element from reading a document
+	///
+	/// Uses the default state type for the returned element.
+	pub fn parse_document<E: Element<'i>>(&mut self) -> Result<E> {
+		self.parse_document_for_state::<E::ParseState>()
+	}
+
+	/// Parse a single (root) element from reading a document
+	///
+	/// Uses the given state type.
+	pub fn parse_document_for_state<S: ElementState<'i>>(&mut self) -> Result<S::Output> {
+		let collect_errors = self.collect_errors;
+		let mut warnings = Vec::new();
+		let result = driver::parse_document::<_, S>(self, collect_errors, None, &mut warnings);
+		self.warnings = warnings;
+		result
+	}
+
+	/// Parse a full document: the root element and everything around it
+	///
+	/// Uses the default state type for the root element.
+	pub fn parse_full_document<E: Element<'i>>(&mut self) -> Result<Document<E>> {
+		self.parse_full_document_for_state::<E::ParseState>()
+	}
+
+	/// Parse a full document: the root element and everything around it
+	///
+	/// Uses the given state type for the root element.
+	pub fn parse_full_document_for_state<S: ElementState<'i>>(
+		&mut self,
+	) -> Result<Document<S::Output>> {
+		let collect_errors = self.collect_errors;
+		let mut document = Document::default();
+		let mut warnings = Vec::new();
+		let result = driver::parse_document::<_, S>(
+			self,
+			collect_errors,
+			Some(&mut document),
+			&mut warnings,
+		);
+		self.warnings = warnings;
+		Ok(document.with_root(result?))
+	}
+}
+
+impl<'i, 'r> Source<'i> for SliceParser<'i, 'r> {
+	fn read_node(&mut self) -> Result<(Position, Node<'i>)> {
+		let event = match self.inner.read_event_unbuffered() {
+			Ok(event) => event,
+			Err(e) => {
+				self.advance_to(self.inner.buffer_position());
+				return Err(Error::from(e).at(self.position));
+			},
+		};
+		if !matches!(event, Event::Text(_) | Event::Eof) {
+			// trimmed whitespace before markup
+			self.skip_whitespace();
+		}
+		let start = self.position;
+		self.advance_to(self.inner.buffer_position());
+		let node = self
+			.node(event, start.offset - self.base)
+			.map_err(|e| e.at(start))?;
+		Ok((start, node))
+	}
+
+	fn position(&self) -> Position {
+		self.position
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{
+		test_struct::*,
+		ErrorKind,
+		Result,
+	};
+	use std::borrow::Cow;
+
+	fn parse<'i, T: super::Element<'i>>(input: &'i str) -> Result<T> {
+		let mut r = quick_xml::Reader::from_str(input);
+		let mut p = super::SliceParser::new(&mut r);
+		p.parse_document::<T>()
+	}
+
+	#[test]
+	fn test() {
+		assert_eq!(
+			parse::<Data>(Data::TEST_PARSE_DOCUMENT_1).unwrap(),
+			Data::TEST_RESULT_1,
+		);
+	}
+
+	#[test]
+	fn test_borrowed() {
+		let input = String::from(Borrowed::TEST_PARSE_DOCUMENT_1);
+		let result = parse::<Borrowed<'_>>(&input).unwrap();
+		assert_eq!(result, Borrowed::TEST_RESULT_1);
+		assert!(matches!(result.cow, Cow::Borrowed(_)));
+		assert!(matches!(result.escaped, Cow::Owned(_)));
+
+		assert!(parse::<Borrowed<'_>>(r#"<borrowed key="a&amp;b" cow="" escaped=""/>"#).is_err());
+	}
+
+	#[test]
+	fn test_started_reader() {
+		// tags are borrowed relative to where the reader was when the adaptor was created
+		let mut r = quick_xml::Reader::from_str(Data::TEST_PARSE_DOCUMENT_1);
+		while !matches!(
+			r.read_event_unbuffered().unwrap(),
+			quick_xml::events::Event::Decl(_)
+		) {}
+		let mut p = super::SliceParser::new(&mut r);
+		assert_eq!(p.parse_document::<Data>().unwrap(), Data::TEST_RESULT_1);
+	}
+
+	#[test]
+	fn test_cdata() {
+		assert_eq!(
+			parse::<Script>(Script::TEST_PARSE_DOCUMENT_1).unwrap(),
+			Script::test_result_1(),
+		);
+		// CDATA content is taken literally (not unescaped)
+		assert_eq!(
+			parse::<Script>("<script><![CDATA[a&amp;b]]></script>")
+				.unwrap()
+				.code,
+			"a&amp;b",
+		);
+	}
+
+	#[test]
+	fn test_positions() {
+		assert_eq!(
+			parse::<SpannedItems>(SpannedItems::TEST_PARSE_DOCUMENT_1).unwrap(),
+			SpannedItems::test_result_1(),
+		);
+		let e = parse::<SpannedItems>(SpannedItems::TEST_PARSE_DOCUMENT_INVALID).unwrap_err();
+		assert_eq!(
+			e.position(),
+			Some(SpannedItems::TEST_ERROR_POSITION_INVALID)
+		);
+		assert_eq!(
+			e.to_string(),
+			r#"line 3, column 2: /items/item[2]: Missing attribute "value""#
+		);
+		let e = parse::<Items>("<items>\n\t<item value=\"1\">\n</items>").unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::Syntax(_)));
+		assert_eq!(e.position().unwrap().line, 3);
+	}
+
+	#[test]
+	fn test_lenient() {
+		parse::<LenientItems>(LenientItems::TEST_PARSE_DOCUMENT_1)
+			.unwrap()
+			.assert_result_1();
+
+		// fatal errors still fail
+		assert!(parse::<LenientItems>(r#"<items><item value="x">"#).is_err());
+	}
+
+	#[test]
+	fn test_full_document() {
+		let mut r = quick_xml::Reader::from_str(TEST_PARSE_FULL_DOCUMENT_1);
+		let mut p = super::SliceParser::new(&mut r);
+		assert_eq!(
+			p.parse_full_document::<Item>().unwrap(),
+			test_full_document_1()
+		);
+	}
+}
@@ -0,0 +1,320 @@
+//! Async adaptors for `tokio`
+//!
+//! Parsing is driven by a [`PushParser`]; serializing uses a
+//! [`DocumentWriter`][super::DocumentWriter] and writes each child of the root element before
+//! serializing the next one.
+
+use super::{
+	push::PushState,
+	Progress,
+	PushParser,
+	Serializer,
+	UnmatchedChildren,
+};
+use crate::{
+	parser::{
+		Element,
+		Warning,
+	},
+	serializer,
+	Error,
+	Result,
+};
+use ::bytes::{
+	Buf,
+	BufMut,
+	BytesMut,
+};
+use ::tokio::io::{
+	AsyncBufRead,
+	AsyncBufReadExt,
+	AsyncWrite,
+	AsyncWriteExt,
+};
+use ::tokio_util::codec::{
+	Decoder,
+	Encoder,
+};
+
+/// Parse document with root element `E` from async reader
+///
+/// Reads until the end of the input (only comments and whitespace may follow the root element).
+pub async fn parse_document<E, R>(reader: &mut R) -> Result<E>
+where
+	E: Element<'static>,
+	R: AsyncBufRead + Unpin,
+{
+	let mut parser = PushParser::<E>::document();
+	let mut root = None;
+	loop {
+		match parser.poll()? {
+			Progress::Element(element) => root = Some(element),
+			Progress::NeedData => {
+				let data = reader.fill_buf().await?;
+				if data.is_empty() {
+					parser.finish();
+				} else {
+					let len = data.len();
+					parser.feed(data);
+					reader.consume(len);
+				}
+			},
+			Progress::Done => {
+				return Ok(root.expect("root element parsed before the end of the document"));
+			},
+		}
+	}
+}
+
+/// Serialize element as document to async writer (and flush it)
+///
+/// The serialized element is buffered before it is written; see [`stream_document`] to write
+/// children as they are serialized.
+pub async fn serialize_document<E, W>(writer: &mut W, element: &E) -> Result<()>
+where
+	E: serializer::Element,
+	W: AsyncWrite + Unpin,
+{
+	stream_document(writer, element, std::iter::empty::<&E>()).await
+}
+
+/// Serialize document with root element `root` and further `children` to async writer (and
+/// flush it)
+///
+/// `root` serializes the attributes (and possibly leading content) of the root element (see
+/// [`Serializer::start_document`] and [`DocumentWriter`][super::DocumentWriter]).  The output is
+/// written after the start of the document and after each child, reusing the same buffer.
+pub async fn stream_document<'c, R, C, W>(
+	writer: &mut W,
+	root: &R,
+	children: impl IntoIterator<Item = &'c C>,
+) -> Result<()>
+where
+	R: serializer::Element,
+	C: serializer::Element + 'c,
+	W: AsyncWrite + Unpin,
+{
+	let mut buf = quick_xml::Writer::new(Vec::new());
+	let mut document = Serializer::new(&mut buf).start_document(root)?;
+	write_buffered(writer, document.inner()).await?;
+	for child in children {
+		document.serialize_element(child)?;
+		write_buffered(writer, document.inner()).await?;
+	}
+	document.finish()?;
+	write_buffered(writer, buf.inner()).await?;
+	writer.flush().await?;
+	Ok(())
+}
+
+/// Write and clear buffer
+async fn write_buffered<W: AsyncWrite + Unpin>(writer: &mut W, buf: &mut Vec<u8>) -> Result<()> {
+	writer.write_all(buf).await?;
+	buf.clear();
+	Ok(())
+}
+
+/// Codec for a stream of elements in a long-lived document
+///
+/// Decodes the children of the root element as `E` (see [`PushParser::children`]); errors
+/// parsing a child are returned as item, other errors end the stream.
+///
+/// Encodes each item as standalone element; the start and end tag of the root element must be
+/// written separately.
+///
+/// Decoding scans the input in the read buffer; only complete children are taken from it.
+pub struct ElementCodec<E> {
+	state: PushState<E>,
+}
+
+impl<E: Element<'static>> ElementCodec<E> {
+	/// New codec; children `E` doesn't accept are handled according to `unmatched`
+	pub fn new(unmatched: UnmatchedChildren) -> Self {
+		Self {
+			state: PushState::new(Some(unmatched)),
+		}
+	}
+
+	/// Take warnings recorded so far
+	pub fn take_warnings(&mut self) -> Vec<Warning> {
+		self.state.take_warnings()
+	}
+
+	fn next(&mut self, src: &mut BytesMut) -> Result<Option<Result<E>>> {
+		let mut consumed = 0;
+		let result = self.state.poll(src, &mut consumed);
+		src.advance(consumed);
+		match result {
+			Ok(Progress::Element(element)) => Ok(Some(Ok(element))),
+			Ok(Progress::NeedData) | Ok(Progress::Done) => Ok(None),
+			Err(e) if self.state.is_done() => Err(e),
+			Err(e) => Ok(Some(Err(e))),
+		}
+	}
+}
+
+impl<E: Element<'static>> Decoder for ElementCodec<E> {
+	type Error = Error;
+	type Item = Result<E>;
+
+	fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+		self.next(src)
+	}
+
+	fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+		self.state.finish();
+		self.next(src)
+	}
+}
+
+impl<E, T: serializer::Element> Encoder<T> for ElementCodec<E> {
+	type Error = Error;
+
+	fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<()> {
+		let mut writer = quick_xml::Writer::new(dst.writer());
+		Serializer::new(&mut writer).serialize_element(&item)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{
+		parse_document,
+		serialize_document,
+		stream_document,
+		ElementCodec,
+	};
+	use crate::{
+		quick_xml::UnmatchedChildren,
+		test_struct::*,
+		ErrorKind,
+	};
+	use ::bytes::BytesMut;
+	use ::tokio::io::{
+		AsyncWriteExt,
+		BufReader,
+	};
+	use ::tokio_util::codec::{
+		Decoder,
+		FramedRead,
+		FramedWrite,
+	};
+	use futures_util::{
+		SinkExt,
+		StreamExt,
+	};
+
+	fn items() -> Items {
+		Items {
+			items: vec![Item { value: 1 }, Item { value: 2 }],
+		}
+	}
+
+	#[tokio::test]
+	async fn test_document() {
+		// small buffer: the document doesn't fit in one chunk
+		let (client, server) = ::tokio::io::duplex(8);
+		let write = async move {
+			let mut client = client;
+			serialize_document(&mut client, &items()).await.unwrap();
+		};
+		let read = async move { parse_document::<Items, _>(&mut BufReader::new(server)).await };
+		let ((), parsed) = ::tokio::join!(write, read);
+		assert_eq!(parsed.unwrap(), items());
+
+		let e = parse_document::<Items, _>(&mut &b"<items>"[..])
+			.await
+			.unwrap_err();
+		assert!(matches!(e.kind(), ErrorKind::UnexpectedEof { .. }));
+	}
+
+	#[tokio::test]
+	async fn test_stream_document() {
+		let (client, server) = ::tokio::io::duplex(8);
+		let write = async move {
+			let mut client = client;
+			let root = ItemsHeader { count: 2 };
+			let children = [Item { value: 1 }, Item { value: 2 }];
+			let future = stream_document(&mut client, &root, &children);
+			// usable in spawned tasks
+			fn assert_send<T: Send>(t: T) -> T {
+				t
+			}
+			assert_send(future).await.unwrap();
+		};
+		let read = async move {
+			FramedRead::new(server, ElementCodec::<Item>::new(UnmatchedChildren::Skip))
+				.map(|item| item.unwrap().unwrap())
+				.collect::<Vec<_>>()
+				.await
+		};
+		let ((), items) = ::tokio::join!(write, read);
+		assert_eq!(items, [Item { value: 1 }, Item { value: 2 }]);
+	}
+
+	#[test]
+	fn test_decode_in_place() {
+		let mut codec = ElementCodec::<Item>::new(UnmatchedChildren::Skip);
+		let mut src = BytesMut::from(&br#"<items><item value="1"/><item va"#[..]);
+		assert_eq!(
+			codec.decode(&mut src).unwrap().unwrap().unwrap(),
+			Item { value: 1 }
+		);
+		assert!(codec.decode(&mut src).unwrap().is_none());
+		// incomplete children stay in the read buffer
+		assert_eq!(&src[..], b"<item va");
+		src.extend_from_slice(br#"lue="2"/></items>"#);
+		assert_eq!(
+			codec.decode(&mut src).unwrap().unwrap().unwrap(),
+			Item { value: 2 }
+		);
+		assert!(codec.decode_eof(&mut src).unwrap().is_none());
+		assert!(src.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_codec() {
+		let (client, server) = ::tokio::io::duplex(16);
+		let write = async move {
+			let mut client = client;
+			client.write_all(b"<items>").await.unwrap();
+			let mut framed =
+				FramedWrite::new(client, ElementCodec::<Item>::new(UnmatchedChildren::Skip));
+			framed.send(Item { value: 1 }).await.unwrap();
+			framed.send(Item { value: 2 }).await.unwrap();
+			let mut client = framed.into_inner();
+			client
+				.write_all(br#"<item value="x"/><item value="3"/></items>"#)
+				.await
+				.unwrap();
+		};
+		let read = async move {
+			FramedRead::new(server, ElementCodec::<Item>::new(UnmatchedChildren::Skip))
+				.collect::<Vec<_>>()
+				.await
+		};
+		let ((), items) = ::tokio::join!(write, read);
+		let items: Vec<_> = items.into_iter().map(|item| item.unwrap()).collect();
+		assert_eq!(items.len(), 4);
+		assert_eq!(items[0].as_ref().unwrap(), &Item { value: 1 });
+		assert_eq!(items[1].as_ref().unwrap(), &Item { value: 2 });
+		assert_eq!(
+			items[2].as_ref().unwrap_err().path().to_string(),
+			"/items/item[3]/@value"
+		);
+		assert_eq!(items[3].as_ref().unwrap(), &Item { value: 3 });
+
+		// fatal errors end the stream
+		let items = FramedRead::new(
+			&b"<items><item value=\"1\"/>"[..],
+			ElementCodec::<Item>::new(UnmatchedChildren::Skip),
+		)
+		.collect::<Vec<_>>()
+		.await;
+		assert_eq!(items.len(), 2);
+		assert!(matches!(
+			items[1].as_ref().unwrap_err().kind(),
+			ErrorKind::UnexpectedEof { .. }
+		));
+	}
+}
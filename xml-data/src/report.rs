@@ -0,0 +1,294 @@
+//! Render errors against the document they were produced from
+//!
+//! [`Report`] formats an error similar to compiler diagnostics: message, location and path, the
+//! affected source line with the error position underlined, and hints:
+//!
+//! ```text
+//! error: Unexpected element: adress
+//!  --> line 3, column 2 (/person/adress[1])
+//!   |
+//! 3 |     <adress/>
+//!   |     ^^^^^^^^^
+//!   = help: did you mean "address"?
+//!   = note: expected one of: "name", "address"
+//! ```
+//!
+//! With the `miette` feature [`Error`] also implements `miette::Diagnostic`; attach the document
+//! with `miette::Report::with_source_code` to get snippets from `miette` instead.
+
+use crate::{
+	errors::PathSegment,
+	Error,
+	ErrorKind,
+	Position,
+};
+use std::fmt::{
+	self,
+	Write,
+};
+
+/// Error rendered against the document text (see [module docs][`self`])
+///
+/// Errors collected while parsing (see
+/// [`ErrorKind::Multiple`][`crate::ErrorKind::Multiple`]) are rendered one after another.
+pub struct Report<'a> {
+	error: &'a Error,
+	source: &'a str,
+}
+
+impl<'a> Report<'a> {
+	/// Render `error` against `source`, the document text the error was produced from
+	pub fn new(error: &'a Error, source: &'a str) -> Self {
+		Self { error, source }
+	}
+}
+
+impl fmt::Display for Report<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.error.kind() {
+			ErrorKind::Multiple(errors) => {
+				for (ndx, error) in errors.iter().enumerate() {
+					if ndx > 0 {
+						writeln!(f)?;
+					}
+					render(f, error, self.source)?;
+				}
+				Ok(())
+			},
+			_ => render(f, self.error, self.source),
+		}
+	}
+}
+
+/// Names expected instead of an unexpected element or attribute
+fn expected(kind: &ErrorKind) -> &[String] {
+	match kind {
+		ErrorKind::UnexpectedElement { expected, .. }
+		| ErrorKind::UnexpectedAttribute { expected, .. } => expected,
+		_ => &[],
+	}
+}
+
+/// Message without hints (those are rendered separately)
+fn message(kind: &ErrorKind) -> String {
+	match kind {
+		ErrorKind::UnexpectedElement { tag, .. } => format!("Unexpected element: {}", tag),
+		ErrorKind::UnexpectedAttribute { key, .. } => format!("Unexpected attribute: {}", key),
+		kind => kind.to_string(),
+	}
+}
+
+fn render(f: &mut fmt::Formatter<'_>, error: &Error, source: &str) -> fmt::Result {
+	writeln!(f, "error: {}", message(error.kind()))?;
+
+	let path = error.path();
+	let position = error.position();
+	let line = position.and_then(|position| {
+		let text = source.lines().nth(position.line.checked_sub(1)?)?;
+		Some((position, text))
+	});
+	// width of line number column
+	let gutter = line.map_or(0, |(position, _)| position.line.to_string().len());
+
+	match position {
+		Some(position) => {
+			write!(f, "{:w$}--> {}", "", position, w = gutter)?;
+			if !path.is_empty() {
+				write!(f, " ({})", path)?;
+			}
+			writeln!(f)?;
+		},
+		None if !path.is_empty() => writeln!(f, "{:w$}--> {}", "", path, w = gutter)?,
+		None => (),
+	}
+
+	if let Some((position, text)) = line {
+		let (column, len) = underline(error, text, position);
+		writeln!(f, "{:w$} |", "", w = gutter)?;
+		writeln!(f, "{} | {}", position.line, text)?;
+		write!(f, "{:w$} | ", "", w = gutter)?;
+		// keep tabs to align with the source line
+		for c in text.chars().take(column - 1) {
+			f.write_char(if c == '\t' { '\t' } else { ' ' })?;
+		}
+		writeln!(f, "{}", "^".repeat(len))?;
+	}
+
+	if let Some(suggestion) = error.kind().suggestion() {
+		writeln!(
+			f,
+			"{:w$} = help: did you mean {:?}?",
+			"",
+			suggestion,
+			w = gutter
+		)?;
+	}
+	if let Some((first, rest)) = expected(error.kind()).split_first() {
+		write!(
+			f,
+			"{:w$} = note: expected one of: {:?}",
+			"",
+			first,
+			w = gutter
+		)?;
+		for name in rest {
+			write!(f, ", {:?}", name)?;
+		}
+		writeln!(f)?;
+	}
+	Ok(())
+}
+
+/// Find range (1-based column and length in characters) to underline in the source line
+///
+/// Underlines the attribute for attribute errors, the tag for markup, and the rest of the line
+/// otherwise.
+fn underline(error: &Error, text: &str, position: Position) -> (usize, usize) {
+	let column = position.column.max(1);
+	let rest = match text.char_indices().nth(column - 1) {
+		Some((start, _)) => &text[start..],
+		None => return (text.chars().count() + 1, 1),
+	};
+	let tag = match rest.find('>') {
+		Some(end) if rest.starts_with('<') => &rest[..=end],
+		_ if rest.starts_with('<') => rest,
+		_ => {
+			let len = rest.trim_end().chars().count();
+			return (column, len.max(1));
+		},
+	};
+	if let Some(PathSegment::Attribute { key }) = error.path().segments().last() {
+		if let Some((start, len)) = find_attribute(tag, key) {
+			return (column + tag[..start].chars().count(), len);
+		}
+	}
+	(column, tag.chars().count())
+}
+
+/// Find `key="value"` in tag; returns byte offset and length in characters
+fn find_attribute(tag: &str, key: &str) -> Option<(usize, usize)> {
+	for (start, _) in tag.match_indices(key) {
+		let preceded_by_space = tag[..start].ends_with(|c: char| c.is_ascii_whitespace());
+		let after = tag[start + key.len()..].trim_start();
+		if !preceded_by_space || !after.starts_with('=') {
+			continue;
+		}
+		let value = after[1..].trim_start();
+		let quote = value.chars().next()?;
+		let end = match value[1..].find(quote) {
+			Some(end) => tag.len() - value.len() + 1 + end + 1,
+			None => tag.len(),
+		};
+		return Some((start, tag[start..end].chars().count()));
+	}
+	None
+}
+
+#[cfg(feature = "miette")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "miette")))]
+impl miette::Diagnostic for Error {
+	fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+		let mut help = String::new();
+		if let Some(suggestion) = self.kind().suggestion() {
+			write!(help, "did you mean {:?}?", suggestion).ok()?;
+		}
+		if let Some((first, rest)) = expected(self.kind()).split_first() {
+			if !help.is_empty() {
+				help.push(' ');
+			}
+			write!(help, "expected one of: {:?}", first).ok()?;
+			for name in rest {
+				write!(help, ", {:?}", name).ok()?;
+			}
+		}
+		if help.is_empty() {
+			None
+		} else {
+			Some(Box::new(help))
+		}
+	}
+
+	fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+		let position = self.position()?;
+		// without the source only the length of a tag is known (`<` and the name)
+		let (label, len) = match self.path().segments().last() {
+			Some(PathSegment::Element { tag, .. }) => (String::from("here"), 1 + tag.len()),
+			Some(PathSegment::Attribute { key }) => (format!("in attribute {:?}", key), 1),
+			None => (String::from("here"), 1),
+		};
+		let span = miette::LabeledSpan::new(Some(label), position.offset, len);
+		Some(Box::new(std::iter::once(span)))
+	}
+
+	fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn miette::Diagnostic> + 'a>> {
+		match self.kind() {
+			ErrorKind::Multiple(errors) => Some(Box::new(
+				errors.iter().map(|e| e as &dyn miette::Diagnostic),
+			)),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::test_struct::*;
+
+	fn parse_error(input: &'static str) -> Error {
+		let mut r = ::quick_xml::Reader::from_str(input);
+		let mut p = crate::quick_xml::SliceParser::new(&mut r);
+		p.parse_document::<Data>().unwrap_err()
+	}
+
+	#[test]
+	fn test_render() {
+		let input = "<data key=\"\" other=\"1\">\n\t<fooo/>\n</data>";
+		let e = parse_error(input);
+		assert_eq!(
+			Report::new(&e, input).to_string(),
+			"error: Unexpected element: fooo
+ --> line 2, column 2 (/data/fooo[1])
+  |
+2 | \t<fooo/>
+  | \t^^^^^^^
+  = help: did you mean \"foo\"?
+  = note: expected one of: \"foo\"
+"
+		);
+
+		let input = r#"<data key="" other="x"/>"#;
+		let e = parse_error(input);
+		assert_eq!(
+			Report::new(&e, input).to_string(),
+			r#"error: Invalid value: invalid digit found in string
+ --> line 1, column 1 (/data/@other)
+  |
+1 | <data key="" other="x"/>
+  |              ^^^^^^^^^
+"#
+		);
+
+		let e = crate::errors::missing_attribute("key");
+		assert_eq!(
+			Report::new(&e, input).to_string(),
+			"error: Missing attribute \"key\"\n"
+		);
+	}
+
+	#[test]
+	fn test_miette() {
+		use miette::Diagnostic;
+
+		let input = "<data key=\"\" other=\"1\">\n\t<fooo/>\n</data>";
+		let e = parse_error(input);
+		assert_eq!(
+			e.help().unwrap().to_string(),
+			r#"did you mean "foo"? expected one of: "foo""#
+		);
+		let labels: Vec<_> = e.labels().unwrap().collect();
+		assert_eq!(labels.len(), 1);
+		assert_eq!(labels[0].offset(), 25);
+		assert_eq!(labels[0].len(), 5);
+	}
+}
@@ -1,11 +1,30 @@
-use crate::Result;
+use crate::{
+	errors,
+	Name,
+	Result,
+};
 use std::borrow::Cow;
 
 /// Element that can be serialized.
 pub trait Element {
-	/// Tag for XML element
+	/// Tag (local name) for XML element
 	fn tag(&self) -> Cow<'_, str>;
 
+	/// Namespace for XML element
+	///
+	/// The default implementation returns `None` (element not in a namespace).
+	fn namespace(&self) -> Option<Cow<'_, str>> {
+		None
+	}
+
+	/// Preferred prefix for the namespace of the element
+	///
+	/// Serializers use it when they need to declare the namespace; the default implementation
+	/// returns `None` (no preference).
+	fn prefix(&self) -> Option<Cow<'_, str>> {
+		None
+	}
+
 	/// Called by serializer to let an element serialize its attributes and inner data (text and
 	/// further elements).
 	fn serialize<S: Serializer>(&self, serializer: S) -> Result<()>;
@@ -14,15 +33,69 @@ pub trait Element {
 /// Interface to serialize an element.
 ///
 /// An element needs to serialize attributes first, then inner text and elements.
+///
+/// The serializer declares namespaces of elements and attributes and chooses their prefixes.
 pub trait Serializer {
 	/// Add an attribute to the serialized element
-	fn serialize_attribute(&mut self, key: &str, value: Cow<'_, str>) -> Result<()>;
+	fn serialize_attribute(&mut self, name: Name<'_>, value: Cow<'_, str>) -> Result<()>;
+
+	/// Declare preferred prefix for a namespace on the element (before serializing attributes)
+	///
+	/// This is only a hint: serializers may ignore it, e.g. if the namespace already has a prefix
+	/// or the prefix is in use.  The default implementation ignores it.
+	fn declare_namespace(&mut self, prefix: &str, namespace: &str) -> Result<()> {
+		let _ = (prefix, namespace);
+		Ok(())
+	}
+
+	/// Qualified name (`prefix:local`) referring to `name` in attribute values or inner text of
+	/// the element
+	///
+	/// Declares a prefix for the namespace on the element if needed, which is only possible
+	/// before serializing inner text or elements.  The default implementation only supports
+	/// names without namespace.
+	fn qualified_name(&mut self, name: Name<'_>) -> Result<String> {
+		match name.namespace {
+			None => Ok(name.local.into()),
+			Some(namespace) => Err(errors::custom(format!(
+				"serializer doesn't support namespace {:?} in values",
+				namespace
+			))),
+		}
+	}
 
 	/// Add inner text to the element.
 	///
 	/// Must be escaped automatically by the serializer.
 	fn serialize_text(&mut self, text: Cow<'_, str>) -> Result<()>;
 
+	/// Add a CDATA section to the element
+	///
+	/// Text containing `]]>` must be split into multiple sections by the serializer.  The default
+	/// implementation serializes it as (escaped) text.
+	fn serialize_cdata(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.serialize_text(text)
+	}
+
+	/// Add a comment to the element
+	///
+	/// Must fail if the text contains `--` or ends with `-`.  The default implementation drops the
+	/// comment.
+	fn serialize_comment(&mut self, text: Cow<'_, str>) -> Result<()> {
+		super::text::check_comment(&text)
+	}
+
+	/// Add a processing instruction (`<?target data?>`) to the element
+	///
+	/// Must fail if the target is invalid (or `xml`) or the data contains `?>`.  The default
+	/// implementation fails with an error.
+	fn serialize_pi(&mut self, target: &str, data: &str) -> Result<()> {
+		super::text::check_pi(target, data)?;
+		Err(errors::custom(
+			"serializer doesn't support processing instructions",
+		))
+	}
+
 	/// Add an inner element
 	///
 	/// The serializer will need to determine the `Element::tag` of the element and call its
@@ -0,0 +1,27 @@
+use crate::{
+	serializer::{
+		Element,
+		Serializer,
+	},
+	Name,
+	Result,
+};
+use std::borrow::Cow;
+
+/// Serializable element without any content (attributes, sub elements or text) with the given name
+pub struct EmptyElement<'a>(pub Name<'a>);
+
+impl Element for EmptyElement<'_> {
+	fn tag(&self) -> Cow<'_, str> {
+		Cow::Borrowed(self.0.local)
+	}
+
+	fn namespace(&self) -> Option<Cow<'_, str>> {
+		self.0.namespace.map(Cow::Borrowed)
+	}
+
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<()> {
+		let _ = serializer;
+		Ok(())
+	}
+}
@@ -7,11 +7,17 @@ use crate::{
 };
 use std::borrow::Cow;
 
-/// Serializable element with a fixed tag.
+/// Serializable element with a fixed tag (and namespace).
 pub trait FixedElement {
-	/// Fixed tag
+	/// Fixed tag (local name)
 	const TAG: &'static str;
 
+	/// Fixed namespace (none by default)
+	const NAMESPACE: Option<&'static str> = None;
+
+	/// Preferred prefix for `NAMESPACE` (none by default)
+	const PREFIX: Option<&'static str> = None;
+
 	/// Same as `Element::serialize`.
 	fn serialize<S: Serializer>(&self, serializer: S) -> Result<()>;
 }
@@ -21,6 +27,14 @@ impl<E: FixedElement> Element for E {
 		Cow::Borrowed(Self::TAG)
 	}
 
+	fn namespace(&self) -> Option<Cow<'_, str>> {
+		Self::NAMESPACE.map(Cow::Borrowed)
+	}
+
+	fn prefix(&self) -> Option<Cow<'_, str>> {
+		Self::PREFIX.map(Cow::Borrowed)
+	}
+
 	fn serialize<S: Serializer>(&self, serializer: S) -> Result<()> {
 		<Self as FixedElement>::serialize(self, serializer)
 	}
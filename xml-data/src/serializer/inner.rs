@@ -26,6 +26,12 @@ impl Inner for Cow<'_, str> {
 	}
 }
 
+impl Inner for &str {
+	fn serialize_elements<S: Serializer>(&self, serializer: &mut S) -> Result<()> {
+		serializer.serialize_text(Cow::Borrowed(self))
+	}
+}
+
 /// Simply serialize the element
 impl<E: Element> Inner for E {
 	fn serialize_elements<S: Serializer>(&self, serializer: &mut S) -> Result<()> {
@@ -43,6 +49,17 @@ impl<I: Inner> Inner for Option<I> {
 	}
 }
 
+/// Serialize inner data if parsing succeeded (see
+/// [`ParseElementListLenient`][`crate::parser::ParseElementListLenient`])
+impl<I: Inner> Inner for Result<I> {
+	fn serialize_elements<S: Serializer>(&self, serializer: &mut S) -> Result<()> {
+		if let Ok(i) = self {
+			i.serialize_elements(serializer)?;
+		}
+		Ok(())
+	}
+}
+
 /// Serialize all inner data
 impl<I: Inner> Inner for Vec<I> {
 	fn serialize_elements<S: Serializer>(&self, serializer: &mut S) -> Result<()> {
@@ -52,3 +69,43 @@ impl<I: Inner> Inner for Vec<I> {
 		Ok(())
 	}
 }
+
+/// Serialize inner data produced by an iterator (e.g. computed lazily by `map`)
+///
+/// The iterator is cloned for each serialization.
+#[derive(Clone, Debug)]
+pub struct InnerIter<I>(pub I);
+
+impl<I> Inner for InnerIter<I>
+where
+	I: Iterator + Clone,
+	I::Item: Inner,
+{
+	fn serialize_elements<S: Serializer>(&self, serializer: &mut S) -> Result<()> {
+		for i in self.0.clone() {
+			i.serialize_elements(serializer)?;
+		}
+		Ok(())
+	}
+}
+
+/// Serialize inner data produced by an iterator the function returns (for iterators that can't
+/// be cloned)
+///
+/// The function is called for each serialization.
+#[derive(Clone, Debug)]
+pub struct InnerFn<F>(pub F);
+
+impl<F, I> Inner for InnerFn<F>
+where
+	F: Fn() -> I,
+	I: IntoIterator,
+	I::Item: Inner,
+{
+	fn serialize_elements<S: Serializer>(&self, serializer: &mut S) -> Result<()> {
+		for i in (self.0)() {
+			i.serialize_elements(serializer)?;
+		}
+		Ok(())
+	}
+}
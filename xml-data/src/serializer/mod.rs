@@ -3,13 +3,17 @@
 //! To implement serializing for your data types (mapping to XML elements) you need to implement
 //! `FixedElement` or `Element`.
 //!
-//! If your data type represents multiple elements you need to implement `Inner`.
+//! If your data type represents multiple elements you need to implement `Inner`; [`InnerIter`]
+//! and [`InnerFn`] wrap iterators computing elements lazily.
 //!
 //! To implement serialize adaptors for an XML library you need to implement `Serializer`.
 
 mod core;
+mod empty;
 mod fixed_element;
 mod inner;
+mod spanned;
+mod text;
 mod value;
 
 #[cfg_attr(doc_cfg, doc(cfg(feature = "derive")))]
@@ -24,11 +28,26 @@ pub use self::{
 		Element,
 		Serializer,
 	},
+	empty::EmptyElement,
 	fixed_element::FixedElement,
-	inner::Inner,
+	inner::{
+		Inner,
+		InnerFn,
+		InnerIter,
+	},
+	text::{
+		CDataText,
+		CommentText,
+	},
 	value::{
 		Value,
 		ValueDefault,
+		ValueQName,
 		ValueString,
 	},
 };
+
+pub(crate) use self::text::{
+	check_comment,
+	check_pi,
+};
@@ -0,0 +1,28 @@
+use crate::{
+	serializer::{
+		Element,
+		Serializer,
+	},
+	Result,
+	Spanned,
+};
+use std::borrow::Cow;
+
+/// Serialize the value (the span is ignored)
+impl<E: Element> Element for Spanned<E> {
+	fn tag(&self) -> Cow<'_, str> {
+		self.value.tag()
+	}
+
+	fn namespace(&self) -> Option<Cow<'_, str>> {
+		self.value.namespace()
+	}
+
+	fn prefix(&self) -> Option<Cow<'_, str>> {
+		self.value.prefix()
+	}
+
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<()> {
+		self.value.serialize(serializer)
+	}
+}
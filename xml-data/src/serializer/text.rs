@@ -0,0 +1,133 @@
+use crate::{
+	errors,
+	namespace,
+	serializer::{
+		Element,
+		Serializer,
+	},
+	Name,
+	Result,
+};
+use std::borrow::Cow;
+
+/// Fail unless `text` can be written as comment (no `--` and not ending in `-`)
+pub(crate) fn check_comment(text: &str) -> Result<()> {
+	if text.contains("--") || text.ends_with('-') {
+		return Err(errors::invalid_value(format!(
+			"comment must not contain \"--\" or end with \"-\": {:?}",
+			text
+		)));
+	}
+	Ok(())
+}
+
+/// Fail unless `target` and `data` can be written as processing instruction
+pub(crate) fn check_pi(target: &str, data: &str) -> Result<()> {
+	if !namespace::is_name(target) || target.eq_ignore_ascii_case("xml") {
+		return Err(errors::invalid_value(format!(
+			"invalid processing instruction target: {:?}",
+			target
+		)));
+	}
+	if data.contains("?>") {
+		return Err(errors::invalid_value(format!(
+			"processing instruction data must not contain \"?>\": {:?}",
+			data
+		)));
+	}
+	Ok(())
+}
+
+/// Serializer adaptor writing text as CDATA sections (see [`Serializer::serialize_cdata`])
+///
+/// Wrap a serializer in this to serialize an [`Inner`][`super::Inner`] as CDATA.
+pub struct CDataText<'s, S>(pub &'s mut S);
+
+impl<S: Serializer> Serializer for CDataText<'_, S> {
+	fn serialize_attribute(&mut self, name: Name<'_>, value: Cow<'_, str>) -> Result<()> {
+		self.0.serialize_attribute(name, value)
+	}
+
+	fn declare_namespace(&mut self, prefix: &str, namespace: &str) -> Result<()> {
+		self.0.declare_namespace(prefix, namespace)
+	}
+
+	fn qualified_name(&mut self, name: Name<'_>) -> Result<String> {
+		self.0.qualified_name(name)
+	}
+
+	fn serialize_text(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.0.serialize_cdata(text)
+	}
+
+	fn serialize_cdata(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.0.serialize_cdata(text)
+	}
+
+	fn serialize_comment(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.0.serialize_comment(text)
+	}
+
+	fn serialize_pi(&mut self, target: &str, data: &str) -> Result<()> {
+		self.0.serialize_pi(target, data)
+	}
+
+	fn serialize_element<E: Element>(&mut self, element: &E) -> Result<()> {
+		self.0.serialize_element(element)
+	}
+}
+
+/// Serializer adaptor writing text as comments (see [`Serializer::serialize_comment`])
+///
+/// Wrap a serializer in this to serialize an [`Inner`][`super::Inner`] as comments.
+pub struct CommentText<'s, S>(pub &'s mut S);
+
+impl<S: Serializer> Serializer for CommentText<'_, S> {
+	fn serialize_attribute(&mut self, name: Name<'_>, value: Cow<'_, str>) -> Result<()> {
+		self.0.serialize_attribute(name, value)
+	}
+
+	fn declare_namespace(&mut self, prefix: &str, namespace: &str) -> Result<()> {
+		self.0.declare_namespace(prefix, namespace)
+	}
+
+	fn qualified_name(&mut self, name: Name<'_>) -> Result<String> {
+		self.0.qualified_name(name)
+	}
+
+	fn serialize_text(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.0.serialize_comment(text)
+	}
+
+	fn serialize_cdata(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.0.serialize_comment(text)
+	}
+
+	fn serialize_comment(&mut self, text: Cow<'_, str>) -> Result<()> {
+		self.0.serialize_comment(text)
+	}
+
+	fn serialize_pi(&mut self, target: &str, data: &str) -> Result<()> {
+		self.0.serialize_pi(target, data)
+	}
+
+	fn serialize_element<E: Element>(&mut self, element: &E) -> Result<()> {
+		self.0.serialize_element(element)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_checks() {
+		assert!(check_comment(" fine - really ").is_ok());
+		assert!(check_comment("a--b").is_err());
+		assert!(check_comment("a-").is_err());
+		assert!(check_pi("xml-stylesheet", "href=\"a.xsl\"").is_ok());
+		assert!(check_pi("XML", "").is_err());
+		assert!(check_pi("a b", "").is_err());
+		assert!(check_pi("a", "?>").is_err());
+	}
+}
@@ -1,4 +1,9 @@
-use crate::Result;
+use crate::{
+	errors,
+	namespace::QName,
+	serializer::Serializer,
+	Result,
+};
 use std::borrow::Cow;
 
 /// Trait to serialize attributes and inner text
@@ -8,6 +13,18 @@ use std::borrow::Cow;
 pub trait Value<T> {
 	/// Serialize value to text
 	fn serialize_value(data: &T) -> Result<Cow<'_, str>>;
+
+	/// Serialize value to text for the element currently serialized by `serializer`
+	///
+	/// Needed by values depending on the element context (like prefixes of qualified names);
+	/// the default implementation calls [`serialize_value`][`Value::serialize_value`].
+	fn serialize_value_for<'d, S: Serializer>(
+		data: &'d T,
+		serializer: &mut S,
+	) -> Result<Cow<'d, str>> {
+		let _ = serializer;
+		Self::serialize_value(data)
+	}
 }
 
 /// Implements `Value` for all types implementing `std::fmt::Display`; this is a good default.
@@ -27,3 +44,28 @@ impl<T: AsRef<str>> Value<T> for ValueString {
 		Ok(Cow::Borrowed(data.as_ref()))
 	}
 }
+
+/// Implements `Value` for [`QName`]: the serializer declares a prefix for the namespace if needed
+/// (see [`Serializer::qualified_name`])
+///
+/// Without serializer only names without namespace can be serialized.
+pub struct ValueQName;
+
+impl Value<QName> for ValueQName {
+	fn serialize_value(data: &QName) -> Result<Cow<'_, str>> {
+		match data.namespace {
+			None => Ok(Cow::Borrowed(&data.local)),
+			Some(_) => Err(errors::custom(format!(
+				"can't serialize {} without serializer",
+				data
+			))),
+		}
+	}
+
+	fn serialize_value_for<'d, S: Serializer>(
+		data: &'d QName,
+		serializer: &mut S,
+	) -> Result<Cow<'d, str>> {
+		serializer.qualified_name(data.name()).map(Cow::Owned)
+	}
+}
@@ -1,3 +1,21 @@
+use crate::{
+	document::{
+		Declaration,
+		Misc,
+	},
+	parser::{
+		Inner,
+		InnerParseResult,
+		InnerState,
+		ParseContext,
+		WarningKind,
+	},
+	Document,
+	Position,
+	QName,
+	Span,
+	Spanned,
+};
 use std::borrow::Cow;
 
 #[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -39,3 +57,343 @@ impl Data {
 #[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 #[xml_data("foo", crate(crate), ignore_unknown)]
 pub struct Foo;
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("flags", crate(crate))]
+pub struct Flags {
+	#[xml_data(attr_flag("disabled", true_value = "disabled", false_value = ""))]
+	pub disabled: bool,
+	#[xml_data(attr_flag)]
+	pub hidden: bool,
+	#[xml_data(flag("enabled"))]
+	pub enabled: bool,
+	#[xml_data(flag)]
+	pub debug: bool,
+}
+
+impl Flags {
+	pub const TEST_PARSE_DOCUMENT_1: &'static str = r#"<?xml version="1.1" encoding="utf-8"?>
+<flags disabled="disabled" hidden="false">
+	<enabled/>
+</flags>"#;
+	pub const TEST_RESULT_1: Self = Self {
+		disabled: true,
+		hidden: false,
+		enabled: true,
+		debug: false,
+	};
+	pub const TEST_SERIALIZE_DOCUMENT_1: &'static str =
+		r#"<?xml version="1.1" encoding="utf-8"?><flags disabled="disabled"><enabled/></flags>"#;
+}
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("borrowed", crate(crate))]
+pub struct Borrowed<'a> {
+	#[xml_data(attr)]
+	pub key: &'a str,
+	#[xml_data(attr)]
+	pub cow: Cow<'a, str>,
+	#[xml_data(attr)]
+	pub escaped: Cow<'a, str>,
+	pub text: &'a str,
+}
+
+impl Borrowed<'_> {
+	pub const TEST_PARSE_DOCUMENT_1: &'static str =
+		r#"<borrowed key="abc" cow="def" escaped="&lt;&gt;">text</borrowed>"#;
+	pub const TEST_RESULT_1: Borrowed<'static> = Borrowed {
+		key: "abc",
+		cow: Cow::Borrowed("def"),
+		escaped: Cow::Borrowed("<>"),
+		text: "text",
+	};
+}
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("items", crate(crate))]
+pub struct Items {
+	pub items: Vec<Item>,
+}
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("item", crate(crate))]
+pub struct Item {
+	#[xml_data(attr)]
+	pub value: u32,
+}
+
+/// Attributes of `<items>` (for streaming the items)
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("items", crate(crate))]
+pub struct ItemsHeader {
+	#[xml_data(attr)]
+	pub count: u32,
+}
+
+impl Items {
+	pub const TEST_PARSE_DOCUMENT_1: &'static str =
+		r#"<items><item value="1"/><item value="2"/></items>"#;
+	pub const TEST_PARSE_DOCUMENT_INVALID: &'static str =
+		r#"<items><item value="1"/><item value="x"/></items>"#;
+
+	pub fn test_result_1() -> Self {
+		Self {
+			items: vec![Item { value: 1 }, Item { value: 2 }],
+		}
+	}
+}
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("items", crate(crate))]
+pub struct SpannedItems {
+	pub items: Vec<Spanned<Item>>,
+}
+
+impl SpannedItems {
+	pub const TEST_ERROR_POSITION_INVALID: Position = Position {
+		offset: 28,
+		line: 3,
+		column: 2,
+	};
+	pub const TEST_PARSE_DOCUMENT_1: &'static str =
+		"<items>\n\t<item value=\"1\"/>\n\t<item value=\"2\">\n\t</item>\n</items>";
+	pub const TEST_PARSE_DOCUMENT_INVALID: &'static str =
+		"<items>\n\t<item value=\"1\"/>\n\t<item/>\n</items>";
+
+	pub fn test_result_1() -> Self {
+		let pos = |offset, line, column| Position {
+			offset,
+			line,
+			column,
+		};
+		Self {
+			items: vec![
+				Spanned {
+					span: Span {
+						start: pos(9, 2, 2),
+						end: pos(26, 2, 19),
+					},
+					value: Item { value: 1 },
+				},
+				Spanned {
+					span: Span {
+						start: pos(28, 3, 2),
+						end: pos(53, 4, 9),
+					},
+					value: Item { value: 2 },
+				},
+			],
+		}
+	}
+}
+
+#[derive(crate::Element, Debug)]
+#[xml_data("items", crate(crate))]
+pub struct LenientItems {
+	pub items: Vec<crate::Result<Item>>,
+}
+
+impl LenientItems {
+	pub const TEST_PARSE_DOCUMENT_1: &'static str = r#"<items>
+	<item value="1"/>
+	<item value="x"><sub><item value="2"/></sub></item>
+	<item value="3"><bogus><deep/></bogus></item>
+	<item value="4"/>
+</items>"#;
+
+	/// Check the result of parsing `TEST_PARSE_DOCUMENT_1` (errors can't be compared)
+	pub fn assert_result_1(&self) {
+		let items = &self.items;
+		assert_eq!(items.len(), 4);
+		assert_eq!(items[0].as_ref().unwrap(), &Item { value: 1 });
+		let e = items[1].as_ref().unwrap_err();
+		assert!(matches!(e.kind(), crate::ErrorKind::InvalidValue(_)));
+		assert_eq!(e.position().unwrap().line, 3);
+		let e = items[2].as_ref().unwrap_err();
+		assert!(
+			matches!(e.kind(), crate::ErrorKind::UnexpectedElement { tag, .. } if tag == "bogus")
+		);
+		assert_eq!(items[3].as_ref().unwrap(), &Item { value: 4 });
+	}
+}
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data(
+	"feed",
+	crate(crate),
+	ns = "http://www.w3.org/2005/Atom",
+	prefix = "atom"
+)]
+pub struct Feed {
+	#[xml_data(attr("lang", ns = "http://www.w3.org/XML/1998/namespace"))]
+	pub lang: String,
+	#[xml_data(attr("id", ns = "urn:vendor", prefix = "v"))]
+	pub vendor_id: u32,
+	#[xml_data(flag("draft"))]
+	pub draft: bool,
+	#[xml_data(flag("hidden"), ns = "urn:vendor")]
+	pub hidden: bool,
+	pub entries: Vec<Entry>,
+}
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("entry", crate(crate), ns = "http://www.w3.org/2005/Atom")]
+pub struct Entry {
+	#[xml_data(attr)]
+	pub id: u32,
+}
+
+impl Feed {
+	pub const TEST_PARSE_DOCUMENT_1: &'static str = r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:x="urn:vendor" xml:lang="en" x:id="7">
+	<draft/><x:hidden/><entry id="1"/><entry id="2"/>
+</feed>"#;
+	pub const TEST_SERIALIZE_DOCUMENT_1: &'static str = r#"<?xml version="1.1" encoding="utf-8"?><atom:feed xmlns:atom="http://www.w3.org/2005/Atom" xmlns:v="urn:vendor" xml:lang="en" v:id="7"><atom:draft/><v:hidden/><atom:entry id="1"/><atom:entry id="2"/></atom:feed>"#;
+
+	pub fn test_result_1() -> Self {
+		Self {
+			lang: "en".into(),
+			vendor_id: 7,
+			draft: true,
+			hidden: true,
+			entries: vec![Entry { id: 1 }, Entry { id: 2 }],
+		}
+	}
+}
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("value", crate(crate))]
+pub struct Typed {
+	#[xml_data(attr(
+		"type",
+		ns = "http://www.w3.org/2001/XMLSchema-instance",
+		prefix = "xsi"
+	))]
+	pub kind: QName,
+	pub items: Vec<Typed>,
+}
+
+impl Typed {
+	pub const TEST_PARSE_DOCUMENT_1: &'static str = r#"<value xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:t="urn:types" xsi:type="t:Order">
+	<value xmlns:t="urn:other" xsi:type="t:Item"/>
+	<value xsi:type="t:Item"/>
+</value>"#;
+	pub const TEST_SERIALIZE_DOCUMENT_1: &'static str = r#"<?xml version="1.1" encoding="utf-8"?><value xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:ns0="urn:types" xsi:type="ns0:Order"><value xmlns:ns1="urn:other" xsi:type="ns1:Item"/><value xsi:type="ns0:Item"/></value>"#;
+
+	pub fn test_result_1() -> Self {
+		let item = |namespace| Self {
+			kind: QName::new(Some(namespace), "Item"),
+			items: Vec::new(),
+		};
+		Self {
+			kind: QName::new(Some("urn:types"), "Order"),
+			items: vec![item("urn:other"), item("urn:types")],
+		}
+	}
+}
+
+#[derive(crate::Element, Debug)]
+#[xml_data("q", crate(crate))]
+pub struct QualifiedValue {
+	#[xml_data(attr("type"))]
+	pub kind: QName,
+	#[xml_data(attr)]
+	pub n: u32,
+}
+
+#[derive(crate::Element, Debug)]
+#[xml_data("values", crate(crate))]
+pub struct LenientQualifiedValues {
+	pub values: Vec<crate::Result<QualifiedValue>>,
+}
+
+#[derive(crate::Element, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[xml_data("script", crate(crate))]
+pub struct Script {
+	#[xml_data(comment)]
+	pub note: String,
+	#[xml_data(cdata)]
+	pub code: String,
+}
+
+impl Script {
+	pub const TEST_PARSE_DOCUMENT_1: &'static str =
+		"<script><!-- generated --><![CDATA[if (a ]]]]><![CDATA[> b) {}]]></script>";
+	pub const TEST_SERIALIZE_DOCUMENT_1: &'static str = r#"<?xml version="1.1" encoding="utf-8"?><script><!-- generated --><![CDATA[if (a ]]]]><![CDATA[> b) {}]]></script>"#;
+
+	pub fn test_result_1() -> Self {
+		Self {
+			note: " generated ".into(),
+			code: "if (a ]]> b) {}".into(),
+		}
+	}
+}
+
+/// Counts inner texts dropped with a warning
+#[derive(Default, Debug)]
+pub struct SkippedText(pub usize);
+
+impl<'a> InnerState<'a> for SkippedText {
+	type Output = Self;
+
+	fn parse_inner_text(
+		&mut self,
+		text: Cow<'a, str>,
+		context: &mut dyn ParseContext,
+	) -> crate::Result<InnerParseResult<Cow<'a, str>>> {
+		let text = text.trim();
+		if !text.is_empty() {
+			self.0 += 1;
+			context.warn(WarningKind::IgnoredText {
+				text: text.to_string(),
+			});
+		}
+		Ok(InnerParseResult::Success)
+	}
+
+	fn parse_inner_finish(self) -> crate::Result<Self::Output> {
+		Ok(self)
+	}
+}
+
+impl<'a> Inner<'a> for SkippedText {
+	type ParseState = Self;
+}
+
+#[derive(crate::parser::Element, Debug)]
+#[xml_data("note", crate(crate))]
+pub struct Note {
+	pub text: SkippedText,
+}
+
+pub const TEST_PARSE_FULL_DOCUMENT_1: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<!-- head -->
+<?xml-stylesheet href="a.xsl"?>
+<!DOCTYPE item [<!ELEMENT item EMPTY>]>
+<!--after--><item value="1"/>
+<?tail?>
+"#;
+pub const TEST_SERIALIZE_FULL_DOCUMENT_1: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><!-- head --><?xml-stylesheet href="a.xsl"?><!DOCTYPE item [<!ELEMENT item EMPTY>]><!--after--><item value="1"/><?tail?>"#;
+
+pub fn test_full_document_1() -> Document<Item> {
+	Document {
+		declaration: Some(Declaration {
+			version: "1.0".into(),
+			encoding: Some("UTF-8".into()),
+			standalone: Some(true),
+		}),
+		prolog: vec![
+			Misc::Comment(" head ".into()),
+			Misc::ProcessingInstruction {
+				target: "xml-stylesheet".into(),
+				data: r#"href="a.xsl""#.into(),
+			},
+		],
+		doctype: Some("item [<!ELEMENT item EMPTY>]".into()),
+		after_doctype: vec![Misc::Comment("after".into())],
+		root: Item { value: 1 },
+		epilog: vec![Misc::ProcessingInstruction {
+			target: "tail".into(),
+			data: String::new(),
+		}],
+	}
+}
@@ -6,13 +6,13 @@ use crate::{
 /// Combining [`parser::Element`] and [`serializer::Element`].
 ///
 /// Can be derived (if `derive` feature is active).
-pub trait Element: parser::Element + serializer::Element {}
+pub trait Element<'a>: parser::Element<'a> + serializer::Element {}
 
-impl<E: parser::Element + serializer::Element> Element for E {}
+impl<'a, E: parser::Element<'a> + serializer::Element> Element<'a> for E {}
 
 /// Combining [`parser::Inner`] and [`serializer::Inner`].
 ///
 /// Can be derived (if `derive` feature is active).
-pub trait Inner: parser::Inner + serializer::Inner {}
+pub trait Inner<'a>: parser::Inner<'a> + serializer::Inner {}
 
-impl<E: parser::Inner + serializer::Inner> Inner for E {}
+impl<'a, E: parser::Inner<'a> + serializer::Inner> Inner<'a> for E {}